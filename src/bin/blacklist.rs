@@ -0,0 +1,81 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use dotenv::dotenv;
+use std::env;
+use std::process;
+use std::str::FromStr;
+use monitor::blacklist::{self, Blacklist, BlacklistKind};
+
+const USAGE: &str = "usage:
+  blacklist list
+  blacklist add <creator|mint|funder> <address> [reason]
+  blacklist remove <creator|mint|funder> <address>
+  blacklist funder <creator> [add]";
+
+#[tokio::main]
+async fn main() {
+
+    dotenv().ok();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let blacklist = Blacklist::load_from_env();
+
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["list"] => {
+            let entries = blacklist.entries();
+            for kind in [BlacklistKind::Creator, BlacklistKind::Mint, BlacklistKind::Funder] {
+                for address in entries.set(kind) {
+                    println!("{}:{}", kind, address);
+                }
+            }
+        },
+        ["add", kind, address, reason @ ..] => {
+            let (kind, address) = parse_entry(kind, address);
+            match blacklist.add(kind, address, &reason.join(" ")) {
+                Ok(true) => println!("Added {} {}", kind, address),
+                Ok(false) => println!("{} {} is already blacklisted", kind, address),
+                Err(err) => exit(&format!("Failed to add {} {}: {}", kind, address, err)),
+            }
+        },
+        ["remove", kind, address] => {
+            let (kind, address) = parse_entry(kind, address);
+            match blacklist.remove(kind, address) {
+                Ok(true) => println!("Removed {} {}", kind, address),
+                Ok(false) => println!("{} {} is not blacklisted", kind, address),
+                Err(err) => exit(&format!("Failed to remove {} {}: {}", kind, address, err)),
+            }
+        },
+        // Look up the wallet that funded a creator, optionally blacklisting it
+        ["funder", creator, rest @ ..] if rest.is_empty() || rest == ["add"] => {
+            let (_, creator) = parse_entry("creator", creator);
+            let rpc_client = RpcClient::new(env::var("RPC_ENDPOINT").unwrap());
+
+            let funder = match blacklist::funding_wallet(&rpc_client, &creator).await {
+                Ok(Some(funder)) => funder,
+                Ok(None) => exit(&format!("No funding wallet found for {}", creator)),
+                Err(err) => exit(&format!("Failed to look up the funding wallet of {}: {}", creator, err)),
+            };
+            println!("Creator {} was funded by {}", creator, funder);
+
+            if rest == ["add"] {
+                match blacklist.add(BlacklistKind::Funder, funder, &format!("funded {}", creator)) {
+                    Ok(true) => println!("Added funder {}", funder),
+                    Ok(false) => println!("funder {} is already blacklisted", funder),
+                    Err(err) => exit(&format!("Failed to add funder {}: {}", funder, err)),
+                }
+            }
+        },
+        _ => exit(USAGE),
+    }
+}
+
+fn parse_entry(kind: &str, address: &str) -> (BlacklistKind, Pubkey) {
+    let kind = BlacklistKind::from_str(kind).unwrap_or_else(|err| exit(&err));
+    let address = Pubkey::from_str(address).unwrap_or_else(|_| exit(&format!("Invalid address {}", address)));
+    (kind, address)
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
@@ -2,7 +2,7 @@ use futures_util::StreamExt;
 use solana_client::nonblocking::pubsub_client::PubsubClient;
 use solana_client::rpc_config::RpcTransactionLogsFilter;
 use solana_client::rpc_config::RpcTransactionLogsConfig;
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_config::RpcTransactionConfig;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::commitment_config::CommitmentLevel;
@@ -11,18 +11,70 @@ use solana_sdk::pubkey::Pubkey;
 use solana_transaction_status::UiTransactionEncoding;
 use solana_transaction_status::UiMessage;
 use solana_transaction_status::EncodedTransaction;
-use spl_token::state::Mint;
-use solana_program::program_pack::Pack;
+use std::fmt::Write;
 use std::str::FromStr;
+use std::sync::Arc;
 use dotenv::dotenv;
 use std::env;
-use bs58;
+use monitor::blacklist::{Blacklist, DevDumpWatcher};
+use monitor::blockhash::BlockhashCache;
+use monitor::config::{env_or, load_keypair};
+use monitor::events::{CreateEvent, TradeEvent};
+use monitor::executor::Executor;
+use monitor::filters::FilterScheduler;
+use monitor::launch::{CreateArgs, NewToken, CREATE_MINT_ACCOUNT, CREATE_USER_ACCOUNT};
+use monitor::logger::console_color_initialize;
+use monitor::metadata::{metadata_address, Metadata};
+use monitor::mint::MintInfo;
+use monitor::offchain::MetadataResolver;
+use monitor::position::{Position, PositionManager, SellConfig};
+use monitor::price_feed::PriceFeed;
+use monitor::pump::PUMP_PROGRAM_ID;
+use monitor::recent::RecentLaunches;
+use monitor::snipe_list::SnipeList;
+use monitor::sniper::{Sniper, SniperConfig};
 
 #[tokio::main]
 async fn main() {
 
     dotenv().ok();
 
+    console_color_initialize();
+
+    let blacklist = if env_or("USE_BLACKLIST", false) {
+        Some(Blacklist::from_env())
+    } else {
+        None
+    };
+    // Blacklist creators selling off their own tokens
+    let dev_dumps = match &blacklist {
+        Some(blacklist) if env_or("AUTO_BLACKLIST_DEV_DUMPS", false) => Some(DevDumpWatcher::from_env(blacklist.clone())),
+        _ => None,
+    };
+
+    // Sniper mode buys every new token and auto sells it
+    let sniper = if env_or("SNIPER", false) {
+        Some(sniper_from_env(blacklist).await)
+    } else {
+        None
+    };
+
+    // Fetch the JSON behind each token uri for its image, description and socials
+    let resolver = if env_or("RESOLVE_METADATA", true) {
+        Some(MetadataResolver::from_env())
+    } else {
+        None
+    };
+
+    let monitor = Arc::new(Monitor {
+        rpc_client: RpcClient::new(env::var("RPC_ENDPOINT").unwrap()),
+        sniper,
+        resolver,
+        // Launches seen recently, to flag tokens reusing what earlier ones had
+        recent_launches: RecentLaunches::from_env(),
+        dev_dumps,
+    });
+
     let ws_url = env::var("RPC_WEBSOCKET_ENDPOINT").unwrap();
     let ws_client = PubsubClient::new(&ws_url).await.unwrap();
 
@@ -39,27 +91,175 @@ async fn main() {
 
     // Process incoming logs
     while let Some(logs) = logs_stream.next().await {
-        // Iterate through logs and find the token creation event
-        for log in logs.value.logs {
+        // Find the token creation event, each new token is handled on its own task so a slow one doesn't hold up the stream.
+        // Failed creates still log it, but no token was made.
+        let created = logs.value.logs.iter().any(|log| log == "Program log: Instruction: Create");
+        if created && logs.value.err.is_none() {
+            let signature = logs.value.signature.clone();
+            match CreateEvent::from_logs(&logs.value.logs) {
+                // The CreateEvent in the logs has everything, tracked right away for the creator's first buy below
+                Some(event) => {
+                    let token = NewToken::from_create_event(signature, &event);
+                    if let Some(dev_dumps) = &monitor.dev_dumps {
+                        dev_dumps.track(&token);
+                    }
+                    tokio::spawn(process_new_token(monitor.clone(), token));
+                },
+                // The transaction is only fetched without it, then its trades are replayed once the token is tracked
+                None => {
+                    let monitor = monitor.clone();
+                    let trades = TradeEvent::from_logs(&logs.value.logs);
+                    tokio::spawn(async move {
+                        let Some(token) = fetch_new_token_from_transaction(&monitor.rpc_client, &signature).await else {
+                            return;
+                        };
+                        if let Some(dev_dumps) = &monitor.dev_dumps {
+                            dev_dumps.track(&token);
+                            for event in &trades {
+                                dev_dumps.on_trade(event);
+                            }
+                        }
+                        process_new_token(monitor, token).await;
+                    });
+                },
+            }
+        }
 
-            if log == "Program log: Instruction: Create" {
-                if let Some(_mint_address) = fetch_mint_address_from_transaction(&logs.value.signature).await {
-                    println!("");
-                }
-                break;
+        // Read after the create is tracked so the creator's first buy counts.
+        // Failed transactions still log their events, but the trades never happened.
+        if let (Some(dev_dumps), None) = (&monitor.dev_dumps, &logs.value.err) {
+            for event in TradeEvent::from_logs(&logs.value.logs) {
+                dev_dumps.on_trade(&event);
             }
+        }
+    }
+
+}
+
+// Everything a new token goes through, shared by the per-token tasks
+struct Monitor {
+    rpc_client: RpcClient,
+    sniper: Option<(Sniper, PositionManager)>,
+    resolver: Option<MetadataResolver>,
+    recent_launches: RecentLaunches,
+    dev_dumps: Option<DevDumpWatcher>,
+}
 
+// Print the new token, resolve its metadata and hand it to the sniper.
+// The token is printed as one block once everything is fetched, so concurrent tokens don't interleave.
+async fn process_new_token(monitor: Arc<Monitor>, mut token: NewToken) {
+    let rpc_client = &monitor.rpc_client;
+    let mut out = String::new();
+
+    let _ = writeln!(out, "New token {} created.", token.mint);
+    let _ = writeln!(out, "Creator: {}", token.creator);
+    if let Some(args) = &token.create_args {
+        let _ = writeln!(out, "Name: {}", args.name);
+        let _ = writeln!(out, "Symbol: {}", args.symbol);
+        let _ = writeln!(out, "URI: {}", args.uri);
+    }
+
+    // Without the create arguments the metadata account is the only source of the name, symbol and uri
+    if token.create_args.is_none() {
+        let metadata = extract_token_metadata(rpc_client, &token.mint, &mut out).await;
+        token = token.with_metadata(metadata);
+    }
+
+    // Unless a filter reads the off-chain metadata, the buy doesn't wait for it to resolve
+    let needs_offchain = monitor.sniper.as_ref().is_some_and(|(sniper, _)| sniper.needs_offchain());
+    if !needs_offchain {
+        spawn_sniper(&monitor, &token);
+    }
+
+    if let (Some(resolver), Some(uri)) = (&monitor.resolver, token.uri().map(str::to_string)) {
+        match resolver.resolve(&uri).await {
+            Ok(offchain) => {
+                let fields = [
+                    ("Image", &offchain.image),
+                    ("Description", &offchain.description),
+                    ("Twitter", &offchain.twitter),
+                    ("Telegram", &offchain.telegram),
+                    ("Website", &offchain.website),
+                ];
+                for (label, value) in fields {
+                    if let Some(value) = value {
+                        let _ = writeln!(out, "{}: {}", label, value);
+                    }
+                }
+                token = token.with_offchain(Some(offchain));
+            },
+            Err(err) => eprintln!("Failed to resolve token metadata {} of {}: {}", uri, token.mint, err),
         }
     }
 
+    token.annotations = monitor.recent_launches.observe(&token);
+    if let Some((handle, mint)) = &token.annotations.twitter_reused {
+        let _ = writeln!(out, "Twitter @{} reused from {}", handle, mint);
+    }
+    for copycat in &token.annotations.copycats {
+        let _ = writeln!(out, "Copycat: {}", copycat);
+    }
+
+    if needs_offchain {
+        spawn_sniper(&monitor, &token);
+    }
+
+    // The mint and metadata accounts only confirm what the create transaction said
+    extract_token_info(rpc_client, token.mint.to_string(), &mut out).await;
+    let metadata = match &token.create_args {
+        Some(_) => extract_token_metadata(rpc_client, &token.mint, &mut out).await,
+        None => token.metadata.clone(),
+    };
+    confirm_create_args(&token, metadata.as_ref(), &mut out);
+    println!("{}", out);
 }
 
-// Function to fetch the mint address from the transaction details
-async fn fetch_mint_address_from_transaction(tx_signature: &String) -> Option<String> {
+// Hand the token to the sniper and manage the position it buys
+fn spawn_sniper(monitor: &Arc<Monitor>, token: &NewToken) {
+    if monitor.sniper.is_none() {
+        return;
+    }
+    let monitor = monitor.clone();
+    let token = token.clone();
+    tokio::spawn(async move {
+        let Some((sniper, positions)) = &monitor.sniper else {
+            return;
+        };
+        if let Some(purchase) = sniper.on_new_token(&token).await {
+            // Released however the position ends, tokens left unsold don't keep ONE_TOKEN_AT_A_TIME locked
+            positions.manage(Position::from(&purchase)).await;
+            sniper.position_closed();
+        }
+    });
+}
 
-    // Create an RPC client to fetch transaction details
+async fn sniper_from_env(blacklist: Option<Arc<Blacklist>>) -> (Sniper, PositionManager) {
     let rpc_url = env::var("RPC_ENDPOINT").unwrap();
-    let rpc_client = RpcClient::new(rpc_url);
+    let rpc_client = Arc::new(RpcClient::new(rpc_url));
+    let blockhash_cache = BlockhashCache::spawn_from_env(rpc_client.clone());
+    let executor = Arc::new(Executor::from_env(rpc_client.clone(), blockhash_cache));
+    let payer = Arc::new(load_keypair());
+
+    let mut sniper = Sniper::new(SniperConfig::from_env(), payer.clone(), rpc_client.clone(), executor.clone())
+        .with_filters(FilterScheduler::from_env());
+    if env_or("USE_SNIPE_LIST", false) {
+        sniper = sniper.with_snipe_list(SnipeList::from_env());
+    }
+    if let Some(blacklist) = blacklist {
+        sniper = sniper.with_blacklist(blacklist);
+    }
+    let mut positions = PositionManager::new(SellConfig::from_env(), payer, rpc_client, executor);
+
+    // Follow open positions over accountSubscribe instead of polling
+    if env_or("PRICE_FEED", true) {
+        let ws_url = env::var("RPC_WEBSOCKET_ENDPOINT").unwrap();
+        positions = positions.with_price_feed(PriceFeed::connect(&ws_url).await.unwrap());
+    }
+    (sniper, positions)
+}
+
+// Function to fetch the new token (mint, creator and create arguments) from the transaction details
+async fn fetch_new_token_from_transaction(rpc_client: &RpcClient, tx_signature: &String) -> Option<NewToken> {
 
     // Decode the base58-encoded signature string to bytes
     let tx_signature_bytes = match bs58::decode(tx_signature).into_vec() {
@@ -88,18 +288,42 @@ async fn fetch_mint_address_from_transaction(tx_signature: &String) -> Option<St
     if let Ok(transaction) = rpc_client.get_transaction_with_config(
         &Signature::from(signature_array),
         config
-    ) {
-        // Parse the transaction details to find the mint address
-        // The exact implementation depends on the transaction format and the structure of the token creation
-
+    ).await {
         match &transaction.transaction.transaction {
             EncodedTransaction::Json(ui_transaction) => {
                 match &ui_transaction.message {
                     UiMessage::Raw(raw_message) => {
-                        if let Some(mint_address) = raw_message.account_keys.get(1) {
-                            println!("New token {} created.", mint_address);
-                            extract_token_info(&rpc_client, mint_address.to_string()).await;
-                            return Some(mint_address.to_string());
+                        let account_keys = &raw_message.account_keys;
+
+                        // Decode the pump.fun create instruction for its arguments and accounts
+                        for instruction in &raw_message.instructions {
+                            if account_keys.get(instruction.program_id_index as usize) != Some(&PUMP_PROGRAM_ID.to_string()) {
+                                continue;
+                            }
+                            let Ok(data) = bs58::decode(&instruction.data).into_vec() else {
+                                continue;
+                            };
+                            let Some(args) = CreateArgs::decode(&data) else {
+                                continue;
+                            };
+                            let account = |index: usize| {
+                                let key = account_keys.get(*instruction.accounts.get(index)? as usize)?;
+                                Pubkey::from_str(key).ok()
+                            };
+                            return Some(NewToken::new(
+                                tx_signature.to_string(),
+                                account(CREATE_MINT_ACCOUNT)?,
+                                account(CREATE_USER_ACCOUNT)?,
+                            ).with_create_args(Some(args)));
+                        }
+
+                        // Created through another program: the creator pays for the transaction and the mint signs it right after
+                        if let (Some(creator), Some(mint_address)) = (account_keys.first(), account_keys.get(1)) {
+                            return Some(NewToken::new(
+                                tx_signature.to_string(),
+                                Pubkey::from_str(mint_address).ok()?,
+                                Pubkey::from_str(creator).ok()?,
+                            ));
                         } else {
                             return None;
                         }
@@ -118,23 +342,71 @@ async fn fetch_mint_address_from_transaction(tx_signature: &String) -> Option<St
 }
 
 // Function to extract the token info from mint address
-async fn extract_token_info(rpc_client: &RpcClient, mint_address: String) {
+async fn extract_token_info(rpc_client: &RpcClient, mint_address: String, out: &mut String) {
 
     let token_mint_pubkey = Pubkey::from_str(&mint_address).unwrap();
 
-    match rpc_client.get_account(&token_mint_pubkey) {
+    match rpc_client.get_account(&token_mint_pubkey).await {
         Ok(account) => {
-            // Parse the account data to get token mint information
-            if let Ok(mint) = Mint::unpack(&account.data) {
-                println!("Mint Authority: {:?}", mint.mint_authority);
-                println!("Supply: {:?}", mint.supply);
-                println!("Decimals: {:?}", mint.decimals);
-                println!("Is Initialized: {:?}", mint.is_initialized);
-                println!("Freeze Authority: {:?}", mint.freeze_authority);
+            // Parse the account data of either token program to get token mint information
+            if let Some(mint) = MintInfo::unpack(&account.owner, &account.data) {
+                let _ = writeln!(out, "Token Program: {}", mint.program);
+                let _ = writeln!(out, "Mint Authority: {:?}", mint.mint_authority);
+                let _ = writeln!(out, "Supply: {:?}", mint.supply);
+                let _ = writeln!(out, "Decimals: {:?}", mint.decimals);
+                let _ = writeln!(out, "Is Initialized: {:?}", mint.is_initialized);
+                let _ = writeln!(out, "Freeze Authority: {:?}", mint.freeze_authority);
+                for extension in &mint.extensions {
+                    let _ = writeln!(out, "Extension: {}{}", extension, if extension.is_risky() { " (risky)" } else { "" });
+                }
             } else {
-                eprintln!("Failed to unpack mint account data");
+                eprintln!("Failed to unpack mint account data of {}", mint_address);
             }
         },
-        Err(err) => eprintln!("Failed to fetch token mint account: {:?}", err),
+        Err(err) => eprintln!("Failed to fetch token mint account {}: {:?}", mint_address, err),
     }
-}
\ No newline at end of file
+}
+
+// Function to fetch and print the Metaplex metadata of the mint
+async fn extract_token_metadata(rpc_client: &RpcClient, mint: &Pubkey, out: &mut String) -> Option<Metadata> {
+
+    let account = match rpc_client.get_account(&metadata_address(mint)).await {
+        Ok(account) => account,
+        Err(err) => {
+            eprintln!("Failed to fetch token metadata account of {}: {:?}", mint, err);
+            return None;
+        }
+    };
+
+    let Some(metadata) = Metadata::decode(&account.data) else {
+        eprintln!("Failed to decode token metadata account data of {}", mint);
+        return None;
+    };
+
+    let _ = writeln!(out, "Update Authority: {}", metadata.update_authority);
+    let _ = writeln!(out, "Is Mutable: {}", metadata.is_mutable);
+    for creator in &metadata.creators {
+        let _ = writeln!(out, "Metadata Creator: {} (share {}%, verified {})", creator.address, creator.share, creator.verified);
+    }
+    Some(metadata)
+}
+
+// Function to compare the metadata account with the create arguments, or print it without them
+fn confirm_create_args(token: &NewToken, metadata: Option<&Metadata>, out: &mut String) {
+
+    let Some(metadata) = metadata else {
+        return;
+    };
+    let Some(args) = &token.create_args else {
+        let _ = writeln!(out, "Name: {}", metadata.name);
+        let _ = writeln!(out, "Symbol: {}", metadata.symbol);
+        let _ = writeln!(out, "URI: {}", metadata.uri);
+        return;
+    };
+
+    if args.name == metadata.name && args.symbol == metadata.symbol && args.uri == metadata.uri {
+        let _ = writeln!(out, "Metadata confirmed");
+    } else {
+        eprintln!("Metadata of {} does not match the create instruction arguments", token.mint);
+    }
+}
@@ -2,17 +2,18 @@ use futures_util::StreamExt;
 use solana_client::nonblocking::pubsub_client::PubsubClient;
 use solana_client::rpc_config::RpcTransactionLogsFilter;
 use solana_client::rpc_config::RpcTransactionLogsConfig;
-use solana_client::rpc_client::RpcClient;
-use solana_client::rpc_config::RpcTransactionConfig;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::commitment_config::CommitmentLevel;
-use solana_sdk::signature::Signature;
-use solana_transaction_status::UiTransactionEncoding;
-use solana_transaction_status::UiMessage;
-use solana_transaction_status::EncodedTransaction;
+use solana_sdk::pubkey::Pubkey;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::broadcast::error::RecvError;
 use dotenv::dotenv;
 use std::env;
-use bs58;
+use std::str::FromStr;
+use std::sync::Arc;
+use monitor::events::TradeEvent;
+use monitor::price_feed::PriceFeed;
+use monitor::watchlist::{parse_entry, WatchList};
 
 #[tokio::main]
 async fn main() {
@@ -22,7 +23,41 @@ async fn main() {
     let ws_url = env::var("RPC_WEBSOCKET_ENDPOINT").unwrap();
     let ws_client = PubsubClient::new(&ws_url).await.unwrap();
 
-    let token_mint = env::var("TOKEN_ADDRESS1").unwrap();
+    // Mints from TOKEN_ADDRESS1, TOKEN_ADDRESSES, WATCH_LIST_FILE and the command line, as <mint>[:<label>]
+    let watch_list = Arc::new(WatchList::from_env());
+    for arg in env::args().skip(1) {
+        watch_list.add_entry(&arg);
+    }
+
+    // Print the token price on every bonding curve update
+    let price_feed = PriceFeed::connect(&ws_url).await.unwrap();
+    for (mint, label) in watch_list.entries() {
+        println!("Watching {} ({})", label, mint);
+        price_feed.watch(mint);
+    }
+    let mut price_updates = price_feed.subscribe();
+    let labels = watch_list.clone();
+    tokio::spawn(async move {
+        loop {
+            match price_updates.recv().await {
+                Ok(update) => {
+                    if let Some(label) = labels.label(&update.mint) {
+                        println!(
+                            " [{}] price {:.10} SOL, market cap {:.2} SOL",
+                            label,
+                            update.price(),
+                            update.curve.market_cap_sol()
+                        );
+                    }
+                },
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    // Change the watch list while running
+    tokio::spawn(read_commands(watch_list.clone(), price_feed.clone()));
 
     // Define the filter for the specific smart contract address
     let filter = RpcTransactionLogsFilter::Mentions(vec!["6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P".to_string()]);
@@ -37,98 +72,64 @@ async fn main() {
 
     // Process incoming logs
     while let Some(logs) = logs_stream.next().await {
-        // Iterate through logs and find the token creation event
-        for log in logs.value.logs {
-
-            if log == "Program log: Instruction: Buy" {
-                if let Some(mint_address) = fetch_mint_address_from_transaction(&logs.value.signature).await {
-                    if token_mint == mint_address {
-                        println!(" token {:?} buy", mint_address);
-                    }
-                }
-                break;
-            }
-
-            else if log == "Program log: Instruction: Sell" {
-                if let Some(mint_address) = fetch_mint_address_from_transaction(&logs.value.signature).await {
-                    if token_mint == mint_address {
-                        println!(" token {:?} sell", mint_address);
-                    }
-                }
-                break;
-            }
+        // Failed transactions still log their events, but the trades never happened
+        if logs.value.err.is_some() {
+            continue;
+        }
 
+        // Every buy and sell emits a TradeEvent with the mint, trader, amounts and reserves after it
+        for trade in TradeEvent::from_logs(&logs.value.logs) {
+            let Some(label) = watch_list.label(&trade.mint) else {
+                continue;
+            };
+
+            println!(
+                " [{}] {} by {}: {:.4} SOL for {:.2} tokens, price {:.10} SOL",
+                label,
+                if trade.is_buy { "buy" } else { "sell" },
+                trade.user,
+                trade.sol(),
+                trade.tokens(),
+                trade.price()
+            );
         }
     }
 
 }
 
-// Function to fetch the mint address from the transaction details
-async fn fetch_mint_address_from_transaction(tx_signature: &String) -> Option<String> {
-
-    let token_mint = env::var("TOKEN_ADDRESS1").unwrap();
-
-    // Create an RPC client to fetch transaction details
-    let rpc_url = env::var("RPC_ENDPOINT").unwrap();
-    let rpc_client = RpcClient::new(rpc_url);
+// Function to read watch list commands from stdin: `add <mint> [label]`, `remove <mint>` and `list`
+async fn read_commands(watch_list: Arc<WatchList>, price_feed: Arc<PriceFeed>) {
 
-    // Decode the base58-encoded signature string to bytes
-    let tx_signature_bytes = match bs58::decode(tx_signature).into_vec() {
-        Ok(bytes) => bytes,
-        Err(_) => return None, // Return None if decoding fails
-    };
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
 
-    // Ensure the byte slice has exactly 64 bytes (required by Solana's Signature type)
-    if tx_signature_bytes.len() != 64 {
-        return None; // Early return if the signature length is not correct
-    }
+    while let Ok(Some(line)) = lines.next_line().await {
+        let line = line.trim();
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
 
-    // Create a fixed-size array of 64 bytes
-    let mut signature_array = [0u8; 64];
-    signature_array.copy_from_slice(&tx_signature_bytes);
-
-    // config
-    let config = RpcTransactionConfig {
-        encoding: Some(UiTransactionEncoding::Json),
-        commitment: Some(CommitmentConfig::confirmed()),
-        max_supported_transaction_version: Some(0),
-    };
-
-
-    // Fetch the transaction details using the signature
-    if let Ok(transaction) = rpc_client.get_transaction_with_config(
-        &Signature::from(signature_array),
-        config
-    ) {
-        // Parse the transaction details to find the mint address
-        // The exact implementation depends on the transaction format and the structure of the token creation
-
-        match &transaction.transaction.transaction {
-            EncodedTransaction::Json(ui_transaction) => {
-                match &ui_transaction.message {
-                    UiMessage::Raw(raw_message) => {
-                        for address in &raw_message.account_keys {
-                            // Check if the value ends with "pump"
-                            if address.ends_with("pump") {
-
-                                if token_mint == address.to_string() {
-                                    println!("{:?}", raw_message);
-                                }
-                                return Some(address.to_string());
-
-                            }
-                        }
-                    },
-                    _ => {
-                        return None;
-                    }
+        match command {
+            "add" => match parse_entry(argument) {
+                Some((mint, label)) => {
+                    watch_list.add(mint, label);
+                    price_feed.watch(mint);
+                    println!("Watching {} ({})", watch_list.label(&mint).unwrap_or_default(), mint);
+                },
+                None => println!("Invalid entry: {}", argument),
+            },
+            "remove" => match Pubkey::from_str(argument.trim()) {
+                Ok(mint) if watch_list.remove(&mint) => {
+                    price_feed.unwatch(&mint);
+                    println!("Stopped watching {}", mint);
+                },
+                Ok(mint) => println!("{} is not watched", mint),
+                Err(_) => println!("Invalid mint: {}", argument),
+            },
+            "list" => {
+                for (mint, label) in watch_list.entries() {
+                    println!("{} {}", mint, label);
                 }
             },
-            _ => {
-                return None;
-            }
+            "" => {},
+            _ => println!("Commands: add <mint> [label], remove <mint>, list"),
         }
     }
-    None
 }
-
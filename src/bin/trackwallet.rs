@@ -1,3 +1,5 @@
+use futures_util::future;
+use futures_util::stream;
 use futures_util::StreamExt;
 use solana_client::nonblocking::pubsub_client::PubsubClient;
 use solana_client::rpc_config::RpcTransactionLogsFilter;
@@ -6,15 +8,50 @@ use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::commitment_config::CommitmentLevel;
 use solana_transaction_status::UiMessage;
 use solana_sdk::signature::Signature;
-use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_transaction_status::UiTransactionEncoding;
 use solana_client::rpc_config::RpcTransactionConfig;
 use solana_transaction_status::EncodedTransaction;
+use solana_transaction_status::UiTransactionTokenBalance;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use std::collections::{HashSet, VecDeque};
 use std::env;
+use std::str::FromStr;
+use std::sync::Arc;
 use dotenv::dotenv;
-use fern::colors::{Color, ColoredLevelConfig};
-use chrono::Local;
-use log::{info};
+use log::{info, warn};
+use monitor::blacklist::Blacklist;
+use monitor::blockhash::BlockhashCache;
+use monitor::config::{env_or, lamports_to_sol, load_keypair};
+use monitor::copy_trade::{CopyConfig, CopyTrader};
+use monitor::events::TradeEvent;
+use monitor::executor::Executor;
+use monitor::logger::console_color_initialize;
+use monitor::pump::{is_trade_instruction, PUMP_PROGRAM_ID, TOKEN_DECIMALS, TRADE_USER_ACCOUNT};
+use monitor::wallets::TrackedWallets;
+
+// A buy or sell by one of the tracked wallets
+struct WalletTrade {
+    wallet: Pubkey,
+    mint: String,
+    // The pump.fun TradeEvent of the wallet's trade
+    event: Option<TradeEvent>,
+    // Network fee of the transaction, priority fee included
+    fee: u64,
+    // Change of the wallet's SOL balance, fees included
+    sol_change: i64,
+    // The wallet's raw token balance after the trade
+    token_balance: Option<u64>,
+}
+
+// How a tracked wallet takes part in a pump.fun transaction
+enum WalletActivity {
+    // The wallet signed it or is the trader of its buy or sell
+    Traded(WalletTrade),
+    // The wallet is only referenced, e.g. as a transfer target or fee recipient
+    Mentioned(Pubkey),
+}
 
 #[tokio::main]
 async fn main() {
@@ -24,198 +61,110 @@ async fn main() {
     console_color_initialize();
 
     let ws_url = env::var("RPC_WEBSOCKET_ENDPOINT").unwrap(); // ws url
-    let program_id_watch = env::var("PROGRAM_PUBLIC_KEY").unwrap(); // program id
-    let watched_wallet_address = env::var("WATCHED_WALLET_ADDRESS").unwrap(); // watch wallet address
-
-    // ws client
-    let ws_client = PubsubClient::new(&ws_url).await.unwrap();
-
-    let filter = RpcTransactionLogsFilter::Mentions(vec![program_id_watch.to_string()]);
-    let config = RpcTransactionLogsConfig { commitment: Some(CommitmentConfig {
-        commitment: CommitmentLevel::Confirmed,
-    }) };
-
-    let (mut subscription, _unsubscribe) = ws_client
-        .logs_subscribe(filter, config)
-        .await.unwrap();
-
-    info!("Monitoring started, fetching the buy/sell transaction for wallet address {}", watched_wallet_address);
-
-    while let Some(logs) = subscription.next().await {
-
-        for log in &logs.value.logs {
-            if log == "Program log: Instruction: Buy" {
-                if let Some(mint_address) = fetch_mint_address_from_transaction(&logs.value.signature.to_string()).await {
-                    info!("detected a buy transaction. Sig: {}", logs.value.signature);
-                    info!("token mint address: {}", mint_address);
-                }
-                break;
-            }
-            else if log == "Program log: Instruction: Sell" {
-                if let Some(mint_address) = fetch_mint_address_from_transaction(&logs.value.signature.to_string()).await {
-                    info!("detected a sell transaction. Sig: {}", logs.value.signature);
-                    info!("token mint address: {}", mint_address);
-                }
-                break;
-            }
-
-        }
+    let program_id_watch = env_or("PROGRAM_PUBLIC_KEY", PUMP_PROGRAM_ID.to_string()); // program id
+    let wallets = TrackedWallets::from_env(); // labelled wallets from WATCHED_WALLETS_FILE and WATCHED_WALLET_ADDRESS
 
+    if wallets.is_empty() {
+        warn!("No wallets to track, set WATCHED_WALLETS_FILE or WATCHED_WALLET_ADDRESS");
+        return;
     }
 
-
-}
-
-
-fn console_color_initialize() {
-    let colors = ColoredLevelConfig::new()
-    .error(Color::Red)
-    .warn(Color::Yellow)
-    .info(Color::Green)
-    .debug(Color::Magenta);
-
-    fern::Dispatch::new()
-        .format(move |out, message, record| {
-            out.finish(format_args!(
-                "{} [{}] {}",
-                Local::now().format("%Y-%m-%d %H:%M:%S"),
-                colors.color(record.level()),
-                message
-            ))
-        })
-        .level(log::LevelFilter::Info)
-        .chain(std::io::stdout())
-        .apply()
-        .unwrap();
-
-}
-
-async fn fetch_mint_address_from_transaction(tx_signature: &String) -> Option<String> {
-
-    // Create an RPC client to fetch transaction details
+    // One client shared by the transaction fetches and the copy trader
     let rpc_url = env::var("RPC_ENDPOINT").unwrap();
-    let rpc_client = RpcClient::new(rpc_url);
-
-    // wallet address
-    let wallet_address = env::var("WATCHED_WALLET_ADDRESS").unwrap();
+    let rpc_client = Arc::new(RpcClient::new(rpc_url));
 
-    // Decode the base58-encoded signature string to bytes
-    let tx_signature_bytes = match bs58::decode(tx_signature).into_vec() {
-        Ok(bytes) => bytes,
-        Err(_) => return None, // Return None if decoding fails
+    // Copy trade mode mirrors the tracked wallets' trades from our wallet
+    let copy_trader = if env_or("COPY_TRADE", false) {
+        Some(Arc::new(copy_trader_from_env(rpc_client.clone())))
+    } else {
+        None
     };
 
-    // Ensure the byte slice has exactly 64 bytes (required by Solana's Signature type)
-    if tx_signature_bytes.len() != 64 {
-        return None; // Early return if the signature length is not correct
-    }
-
-    // Create a fixed-size array of 64 bytes
-    let mut signature_array = [0u8; 64];
-    signature_array.copy_from_slice(&tx_signature_bytes);
-
-    // config
-    let config = RpcTransactionConfig {
-        encoding: Some(UiTransactionEncoding::Json),
-        commitment: Some(CommitmentConfig::confirmed()),
-        max_supported_transaction_version: Some(0),
-    };
-
-
-    // Fetch the transaction details using the signature
-    if let Ok(transaction) = rpc_client.get_transaction_with_config(
-        &Signature::from(signature_array),
-        config
-    ) {
-        match &transaction.transaction.transaction {
-            EncodedTransaction::Json(ui_transaction) => {
-                match &ui_transaction.message {
-                    UiMessage::Raw(raw_message) => {
-                        for address1 in &raw_message.account_keys {
-                            // check wallet address
-                            if address1 == &wallet_address.to_string() {
-                                for address2 in &raw_message.account_keys {
-                                    // Check token
-                                    if address2.ends_with("pump") {
-                                        return Some(address2.to_string());
-                                    }
-                                }
-                            }
-                        }
-                        return None;
-                    },
-                    _ => {
-                        return None;
-                    }
-                }
-            },
-            _ => {
-                return None;
-            }
-        }
-    }
-    None
-}use futures_util::StreamExt;
-use solana_client::nonblocking::pubsub_client::PubsubClient;
-use solana_client::rpc_config::RpcTransactionLogsFilter;
-use solana_client::rpc_config::RpcTransactionLogsConfig;
-use solana_sdk::commitment_config::CommitmentConfig;
-use solana_sdk::commitment_config::CommitmentLevel;
-use solana_transaction_status::UiMessage;
-use solana_sdk::signature::Signature;
-use solana_client::rpc_client::RpcClient;
-use solana_transaction_status::UiTransactionEncoding;
-use solana_client::rpc_config::RpcTransactionConfig;
-use solana_transaction_status::EncodedTransaction;
-use std::env;
-use dotenv::dotenv;
-use fern::colors::{Color, ColoredLevelConfig};
-use chrono::Local;
-use log::{info};
-
-#[tokio::main]
-async fn main() {
-
-    dotenv().ok();
-
-    console_color_initialize();
-
-    let ws_url = env::var("RPC_WEBSOCKET_ENDPOINT").unwrap(); // ws url
-    let program_id_watch = env::var("PROGRAM_PUBLIC_KEY").unwrap(); // program id
-    let watched_wallet_address = env::var("WATCHED_WALLET_ADDRESS").unwrap(); // watch wallet address
+    // Also report buy/sell transactions merely referencing a tracked wallet
+    let track_mentions = env_or("TRACK_MENTIONS", false);
+    // Transactions fetched at once
+    let fetch_concurrency = env_or("TRACK_FETCH_CONCURRENCY", 8usize).max(1);
 
     // ws client
     let ws_client = PubsubClient::new(&ws_url).await.unwrap();
 
-    let filter = RpcTransactionLogsFilter::Mentions(vec![program_id_watch.to_string()]);
     let config = RpcTransactionLogsConfig { commitment: Some(CommitmentConfig {
         commitment: CommitmentLevel::Confirmed,
     }) };
 
-    let (mut subscription, _unsubscribe) = ws_client
-        .logs_subscribe(filter, config)
-        .await.unwrap();
-
-    info!("Monitoring started, fetching the buy/sell transaction for wallet address {}", watched_wallet_address);
-
-    while let Some(logs) = subscription.next().await {
+    // Mentions takes a single address, so one subscription per wallet: only the transactions a tracked wallet takes part in are fetched
+    let mut subscriptions = Vec::new();
+    for wallet in wallets.addresses() {
+        let filter = RpcTransactionLogsFilter::Mentions(vec![wallet.to_string()]);
+        let (subscription, _unsubscribe) = ws_client
+            .logs_subscribe(filter, config.clone())
+            .await.unwrap();
+        subscriptions.push(subscription);
+    }
 
-        for log in &logs.value.logs {
-            if log == "Program log: Instruction: Buy" {
-                if let Some(mint_address) = fetch_mint_address_from_transaction(&logs.value.signature.to_string()).await {
-                    info!("detected a buy transaction. Sig: {}", logs.value.signature);
-                    info!("token mint address: {}", mint_address);
-                }
-                break;
-            }
-            else if log == "Program log: Instruction: Sell" {
-                if let Some(mint_address) = fetch_mint_address_from_transaction(&logs.value.signature.to_string()).await {
-                    info!("detected a sell transaction. Sig: {}", logs.value.signature);
-                    info!("token mint address: {}", mint_address);
+    info!("Monitoring started, fetching the buy/sell transactions of {} wallets", wallets.len());
+
+    let program_invoked = format!("Program {} invoke", program_id_watch);
+    let mut seen = RecentSignatures::new(10_000);
+    let (rpc_client, wallets) = (&rpc_client, &wallets);
+
+    let mut fetched = stream::select_all(subscriptions)
+        .filter_map(|logs| {
+            let logs = logs.value;
+
+            // Failed transactions still log their events, but nothing was traded.
+            // A transaction mentioning several tracked wallets is notified once per wallet.
+            let side = if logs.err.is_some() || !logs.logs.iter().any(|log| log.starts_with(&program_invoked)) || !seen.insert(&logs.signature) {
+                None
+            } else {
+                logs.logs.iter().find_map(|log| match log.as_str() {
+                    "Program log: Instruction: Buy" => Some("buy"),
+                    "Program log: Instruction: Sell" => Some("sell"),
+                    _ => None,
+                })
+            };
+            future::ready(side.map(|side| (logs, side)))
+        })
+        .map(|(logs, side)| async move {
+            // Fetched even when no TradeEvent names a tracked wallet, the logs don't show a wallet signing a trade made through another program
+            let events = TradeEvent::from_logs(&logs.logs);
+            let activity = fetch_trade_from_transaction(rpc_client, &logs.signature, &events, wallets).await;
+            (logs.signature, side, activity)
+        })
+        .buffer_unordered(fetch_concurrency);
+
+    while let Some((signature, side, activity)) = fetched.next().await {
+
+        match activity {
+            Some(WalletActivity::Traded(trade)) => {
+                let label = wallets.label(&trade.wallet).unwrap_or_default();
+                let side = match &trade.event {
+                    Some(event) if event.is_buy => "buy",
+                    Some(_) => "sell",
+                    None => side,
+                };
+                info!("[{}] detected a {} transaction. Sig: {}", label, side, signature);
+                info!("[{}] token mint address: {}", label, trade.mint);
+                print_trade_report(label, &trade);
+
+                // The event is the wallet's own trade, not others bundled in the same transaction
+                if let (Some(copy_trader), Some(event)) = (&copy_trader, &trade.event) {
+                    let copy_trader = copy_trader.clone();
+                    let event = event.clone();
+                    let sizing = wallets.sizing(&trade.wallet);
+                    let leader_balance = trade.token_balance.unwrap_or(0);
+                    tokio::spawn(async move {
+                        if let Err(err) = copy_trader.copy(&event, sizing, leader_balance).await {
+                            warn!("copy trade of {} failed: {}", event.mint, err);
+                        }
+                    });
                 }
-                break;
-            }
-
+            },
+            Some(WalletActivity::Mentioned(wallet)) if track_mentions => {
+                let label = wallets.label(&wallet).unwrap_or_default();
+                info!("[{}] mentioned in a {} transaction it did not trade in. Sig: {}", label, side, signature);
+            },
+            _ => {},
         }
 
     }
@@ -223,38 +172,48 @@ async fn main() {
 
 }
 
+// Signatures already handled, the oldest forgotten past `capacity`
+struct RecentSignatures {
+    order: VecDeque<String>,
+    seen: HashSet<String>,
+    capacity: usize,
+}
 
-fn console_color_initialize() {
-    let colors = ColoredLevelConfig::new()
-    .error(Color::Red)
-    .warn(Color::Yellow)
-    .info(Color::Green)
-    .debug(Color::Magenta);
-
-    fern::Dispatch::new()
-        .format(move |out, message, record| {
-            out.finish(format_args!(
-                "{} [{}] {}",
-                Local::now().format("%Y-%m-%d %H:%M:%S"),
-                colors.color(record.level()),
-                message
-            ))
-        })
-        .level(log::LevelFilter::Info)
-        .chain(std::io::stdout())
-        .apply()
-        .unwrap();
+impl RecentSignatures {
+    fn new(capacity: usize) -> Self {
+        Self { order: VecDeque::new(), seen: HashSet::new(), capacity }
+    }
 
+    // False when the signature was already seen
+    fn insert(&mut self, signature: &str) -> bool {
+        if self.seen.contains(signature) {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.order.push_back(signature.to_string());
+        self.seen.insert(signature.to_string());
+        true
+    }
 }
 
-async fn fetch_mint_address_from_transaction(tx_signature: &String) -> Option<String> {
+fn copy_trader_from_env(rpc_client: Arc<RpcClient>) -> CopyTrader {
+    let blockhash_cache = BlockhashCache::spawn_from_env(rpc_client.clone());
+    let executor = Arc::new(Executor::from_env(rpc_client.clone(), blockhash_cache));
 
-    // Create an RPC client to fetch transaction details
-    let rpc_url = env::var("RPC_ENDPOINT").unwrap();
-    let rpc_client = RpcClient::new(rpc_url);
+    let copy_trader = CopyTrader::new(CopyConfig::from_env(), Arc::new(load_keypair()), rpc_client, executor);
+    if env_or("USE_BLACKLIST", false) {
+        copy_trader.with_blacklist(Blacklist::from_env())
+    } else {
+        copy_trader
+    }
+}
 
-    // wallet address
-    let wallet_address = env::var("WATCHED_WALLET_ADDRESS").unwrap();
+// Function to fetch the transaction and find the tracked wallet trading in it, the traded mint and the trade details
+async fn fetch_trade_from_transaction(rpc_client: &RpcClient, tx_signature: &String, events: &[TradeEvent], wallets: &TrackedWallets) -> Option<WalletActivity> {
 
     // Decode the base58-encoded signature string to bytes
     let tx_signature_bytes = match bs58::decode(tx_signature).into_vec() {
@@ -283,23 +242,67 @@ async fn fetch_mint_address_from_transaction(tx_signature: &String) -> Option<St
     if let Ok(transaction) = rpc_client.get_transaction_with_config(
         &Signature::from(signature_array),
         config
-    ) {
+    ).await {
+        let meta = transaction.transaction.meta.as_ref()?;
+        if meta.err.is_some() {
+            return None;
+        }
+
         match &transaction.transaction.transaction {
             EncodedTransaction::Json(ui_transaction) => {
                 match &ui_transaction.message {
                     UiMessage::Raw(raw_message) => {
-                        for address1 in &raw_message.account_keys {
-                            // check wallet address
-                            if address1 == &wallet_address.to_string() {
-                                for address2 in &raw_message.account_keys {
-                                    // Check token
-                                    if address2.ends_with("pump") {
-                                        return Some(address2.to_string());
-                                    }
-                                }
-                            }
-                        }
-                        return None;
+                        let account_keys = &raw_message.account_keys;
+
+                        // The trader of a pump.fun buy or sell, from its TradeEvent or else its instruction
+                        let instruction_users = raw_message.instructions
+                            .iter()
+                            .filter(|instruction| account_keys.get(instruction.program_id_index as usize) == Some(&PUMP_PROGRAM_ID.to_string()))
+                            .filter(|instruction| bs58::decode(&instruction.data).into_vec().is_ok_and(|data| is_trade_instruction(&data)))
+                            .filter_map(|instruction| account_keys.get(*instruction.accounts.get(TRADE_USER_ACCOUNT)? as usize))
+                            .filter_map(|address| Pubkey::from_str(address).ok());
+                        // Signers come first in the account keys
+                        let signers: Vec<Pubkey> = account_keys[..(raw_message.header.num_required_signatures as usize).min(account_keys.len())]
+                            .iter()
+                            .filter_map(|signer| Pubkey::from_str(signer).ok())
+                            .collect();
+
+                        // pump.fun has the trader sign, a trader that didn't sign comes from a forged event
+                        let traders = events.iter().map(|event| event.user).chain(instruction_users).filter(|trader| signers.contains(trader));
+
+                        let wallet = match traders.chain(signers.iter().copied()).find(|wallet| wallets.contains(wallet)) {
+                            Some(wallet) => wallet,
+                            None => {
+                                // Referenced without trading
+                                let wallet = account_keys
+                                    .iter()
+                                    .filter_map(|address| Pubkey::from_str(address).ok())
+                                    .find(|address| wallets.contains(address))?;
+                                return Some(WalletActivity::Mentioned(wallet));
+                            },
+                        };
+
+                        // Only the wallet's own trade, a signer trading through another program has none
+                        let event = events.iter().find(|event| event.user == wallet).cloned();
+
+                        // Check token
+                        let mint = match &event {
+                            Some(event) => event.mint.to_string(),
+                            None => account_keys.iter().find(|address| address.ends_with("pump"))?.to_string(),
+                        };
+
+                        let wallet_index = account_keys.iter().position(|address| *address == wallet.to_string())?;
+                        let sol_change = *meta.post_balances.get(wallet_index)? as i64 - *meta.pre_balances.get(wallet_index)? as i64;
+
+                        // Sold out token accounts are closed and missing from the post balances
+                        let post_token_balances: Option<&Vec<UiTransactionTokenBalance>> = meta.post_token_balances.as_ref().into();
+                        let token_balance = post_token_balances
+                            .into_iter()
+                            .flatten()
+                            .find(|balance| balance.mint == mint && Option::<&String>::from(balance.owner.as_ref()) == Some(&wallet.to_string()))
+                            .and_then(|balance| balance.ui_token_amount.amount.parse().ok());
+
+                        return Some(WalletActivity::Traded(WalletTrade { wallet, mint, event, fee: meta.fee, sol_change, token_balance }));
                     },
                     _ => {
                         return None;
@@ -312,4 +315,35 @@ async fn fetch_mint_address_from_transaction(tx_signature: &String) -> Option<St
         }
     }
     None
-}
\ No newline at end of file
+}
+
+// Function to print the amounts, price, fees, resulting balance and curve progress of a trade
+fn print_trade_report(label: &str, trade: &WalletTrade) {
+
+    if let Some(event) = &trade.event {
+        info!(
+            "[{}] {} {:.2} tokens for {:.6} SOL at {:.10} SOL per token",
+            label,
+            if event.is_buy { "bought" } else { "sold" },
+            event.tokens(),
+            event.sol(),
+            event.price()
+        );
+        info!(
+            "[{}] fees: {:.6} SOL network, {:.6} SOL pump.fun",
+            label,
+            lamports_to_sol(trade.fee),
+            lamports_to_sol(event.fee_lamports())
+        );
+        info!("[{}] bonding curve {:.2}% sold", label, event.progress());
+    } else {
+        info!("[{}] fees: {:.6} SOL network", label, lamports_to_sol(trade.fee));
+    }
+
+    info!(
+        "[{}] SOL balance change {:+.6}, token balance {:.2}",
+        label,
+        trade.sol_change as f64 / LAMPORTS_PER_SOL as f64,
+        trade.token_balance.unwrap_or(0) as f64 / 10f64.powi(TOKEN_DECIMALS as i32)
+    );
+}
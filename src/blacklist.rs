@@ -0,0 +1,458 @@
+use crate::config::env_or;
+use crate::events::TradeEvent;
+use crate::launch::NewToken;
+use crate::Result;
+use arc_swap::ArcSwap;
+use log::{info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+// Pages of 1000 signatures walked back to find a wallet's first transaction
+const FUNDING_LOOKUP_PAGES: usize = 3;
+const SIGNATURES_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlacklistKind {
+    Creator,
+    Mint,
+    Funder,
+}
+
+impl FromStr for BlacklistKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "creator" => Ok(Self::Creator),
+            "mint" => Ok(Self::Mint),
+            "funder" => Ok(Self::Funder),
+            _ => Err(format!("unknown blacklist kind: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for BlacklistKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Creator => write!(f, "creator"),
+            Self::Mint => write!(f, "mint"),
+            Self::Funder => write!(f, "funder"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlacklistEntries {
+    pub creators: HashSet<Pubkey>,
+    pub mints: HashSet<Pubkey>,
+    pub funders: HashSet<Pubkey>,
+}
+
+// `<kind>:<address>`, anything after a # is a comment
+fn parse_line(line: &str) -> Option<(BlacklistKind, Pubkey)> {
+    let line = line.split('#').next()?.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let entry = line.split_once(':').and_then(|(kind, address)| {
+        Some((kind.parse().ok()?, Pubkey::from_str(address.trim()).ok()?))
+    });
+    if entry.is_none() {
+        warn!("invalid blacklist entry: {}", line);
+    }
+    entry
+}
+
+impl BlacklistEntries {
+    pub fn parse(contents: &str) -> Self {
+        let mut entries = Self::default();
+        for (kind, address) in contents.lines().filter_map(parse_line) {
+            entries.set_mut(kind).insert(address);
+        }
+        entries
+    }
+
+    pub fn set(&self, kind: BlacklistKind) -> &HashSet<Pubkey> {
+        match kind {
+            BlacklistKind::Creator => &self.creators,
+            BlacklistKind::Mint => &self.mints,
+            BlacklistKind::Funder => &self.funders,
+        }
+    }
+
+    fn set_mut(&mut self, kind: BlacklistKind) -> &mut HashSet<Pubkey> {
+        match kind {
+            BlacklistKind::Creator => &mut self.creators,
+            BlacklistKind::Mint => &mut self.mints,
+            BlacklistKind::Funder => &mut self.funders,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.creators.len() + self.mints.len() + self.funders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Creators, mints and funding wallets never to buy from, persisted to a file
+pub struct Blacklist {
+    path: PathBuf,
+    entries: ArcSwap<BlacklistEntries>,
+    // Serializes file writes between the reloader, auto-blacklisting and commands
+    file_lock: Mutex<()>,
+}
+
+impl Blacklist {
+    pub fn load(path: impl AsRef<Path>) -> Arc<Self> {
+        let blacklist = Arc::new(Self {
+            path: path.as_ref().to_path_buf(),
+            entries: ArcSwap::from_pointee(BlacklistEntries::default()),
+            file_lock: Mutex::new(()),
+        });
+        if blacklist.path.exists() {
+            if let Err(err) = blacklist.reload() {
+                warn!(
+                    "failed to load blacklist {}: {}",
+                    blacklist.path.display(),
+                    err
+                );
+            }
+        }
+        blacklist
+    }
+
+    // BLACKLIST_FILE without reloading, for one-off commands
+    pub fn load_from_env() -> Arc<Self> {
+        Self::load(env_or("BLACKLIST_FILE", "blacklist.txt".to_string()))
+    }
+
+    // BLACKLIST_FILE reloaded every BLACKLIST_REFRESH_INTERVAL ms, so entries added from the
+    // blacklist command apply to a running sniper
+    pub fn from_env() -> Arc<Self> {
+        let blacklist = Self::load_from_env();
+        blacklist.spawn_reloader(Duration::from_millis(env_or(
+            "BLACKLIST_REFRESH_INTERVAL",
+            30_000,
+        )));
+        blacklist
+    }
+
+    pub fn reload(&self) -> Result<()> {
+        let _guard = self.file_lock.lock().unwrap();
+        let entries = BlacklistEntries::parse(&std::fs::read_to_string(&self.path)?);
+        if *self.entries.load_full() != entries {
+            info!(
+                "loaded {} blacklist entries from {}",
+                entries.len(),
+                self.path.display()
+            );
+            self.entries.store(Arc::new(entries));
+        }
+        Ok(())
+    }
+
+    // Reload on an interval. The task stops once the blacklist is dropped.
+    pub fn spawn_reloader(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let blacklist: Weak<Self> = Arc::downgrade(self);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(blacklist) = blacklist.upgrade() else {
+                    break;
+                };
+                if !blacklist.path.exists() {
+                    continue;
+                }
+                if let Err(err) = blacklist.reload() {
+                    warn!(
+                        "failed to reload blacklist {}: {}",
+                        blacklist.path.display(),
+                        err
+                    );
+                }
+            }
+        })
+    }
+
+    pub fn entries(&self) -> Arc<BlacklistEntries> {
+        self.entries.load_full()
+    }
+
+    pub fn contains(&self, kind: BlacklistKind, address: &Pubkey) -> bool {
+        self.entries.load().set(kind).contains(address)
+    }
+
+    pub fn has_funders(&self) -> bool {
+        !self.entries.load().funders.is_empty()
+    }
+
+    // The blacklisted mint or creator of a new token
+    pub fn check(&self, token: &NewToken) -> Option<(BlacklistKind, Pubkey)> {
+        let entries = self.entries.load();
+        if entries.mints.contains(&token.mint) {
+            return Some((BlacklistKind::Mint, token.mint));
+        }
+        if entries.creators.contains(&token.creator) {
+            return Some((BlacklistKind::Creator, token.creator));
+        }
+        None
+    }
+
+    // Appends the entry to the file. Returns false if it was already listed.
+    pub fn add(&self, kind: BlacklistKind, address: Pubkey, reason: &str) -> Result<bool> {
+        let _guard = self.file_lock.lock().unwrap();
+        if self.contains(kind, &address) {
+            return Ok(false);
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        // Keep a hand-edited last line without a newline from running into the entry
+        let unterminated = std::fs::read(&self.path)?
+            .last()
+            .is_some_and(|byte| *byte != b'\n');
+        if unterminated {
+            writeln!(file)?;
+        }
+        if reason.is_empty() {
+            writeln!(file, "{}:{}", kind, address)?;
+        } else {
+            writeln!(file, "{}:{} # {}", kind, address, reason)?;
+        }
+
+        let mut entries = BlacklistEntries::clone(&self.entries.load());
+        entries.set_mut(kind).insert(address);
+        self.entries.store(Arc::new(entries));
+        Ok(true)
+    }
+
+    // Drops the entry's lines from the file, keeping everything else as written.
+    // Returns false if it was not listed.
+    pub fn remove(&self, kind: BlacklistKind, address: Pubkey) -> Result<bool> {
+        let _guard = self.file_lock.lock().unwrap();
+        if !self.contains(kind, &address) {
+            return Ok(false);
+        }
+
+        let contents = std::fs::read_to_string(&self.path)?;
+        let kept: Vec<&str> = contents
+            .lines()
+            .filter(|line| parse_line(line) != Some((kind, address)))
+            .collect();
+        let mut contents = kept.join("\n");
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        std::fs::write(&self.path, contents)?;
+
+        let mut entries = BlacklistEntries::clone(&self.entries.load());
+        entries.set_mut(kind).remove(&address);
+        self.entries.store(Arc::new(entries));
+        Ok(true)
+    }
+}
+
+// The fee payer of a wallet's first transaction, usually the wallet that funded it.
+// None when that is the wallet itself or its history is too long to walk.
+pub async fn funding_wallet(rpc_client: &RpcClient, wallet: &Pubkey) -> Result<Option<Pubkey>> {
+    let mut before = None;
+    let mut oldest = None;
+
+    for page in 0..FUNDING_LOOKUP_PAGES {
+        let signatures = rpc_client
+            .get_signatures_for_address_with_config(
+                wallet,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until: None,
+                    limit: Some(SIGNATURES_PAGE_SIZE),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .await?;
+
+        if let Some(last) = signatures.last() {
+            let signature = Signature::from_str(&last.signature)?;
+            oldest = Some(signature);
+            before = Some(signature);
+        }
+        if signatures.len() < SIGNATURES_PAGE_SIZE {
+            break;
+        }
+        if page + 1 == FUNDING_LOOKUP_PAGES {
+            return Ok(None);
+        }
+    }
+
+    let Some(signature) = oldest else {
+        return Ok(None);
+    };
+    let transaction = rpc_client
+        .get_transaction_with_config(
+            &signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+
+    let fee_payer = transaction
+        .transaction
+        .transaction
+        .decode()
+        .and_then(|transaction| transaction.message.static_account_keys().first().copied());
+    Ok(fee_payer.filter(|fee_payer| fee_payer != wallet))
+}
+
+struct DevHolding {
+    creator: Pubkey,
+    bought: u64,
+    sold: u64,
+    created_at: Instant,
+}
+
+// Follows the creator's trades on new tokens and blacklists creators that sell off
+// AUTO_BLACKLIST_DUMP_PERCENT of what they bought within AUTO_BLACKLIST_WINDOW ms of launch
+pub struct DevDumpWatcher {
+    blacklist: Arc<Blacklist>,
+    dump_percent: f64,
+    window: Duration,
+    holdings: Mutex<HashMap<Pubkey, DevHolding>>,
+}
+
+impl DevDumpWatcher {
+    pub fn new(blacklist: Arc<Blacklist>, dump_percent: f64, window: Duration) -> Self {
+        Self {
+            blacklist,
+            dump_percent,
+            window,
+            holdings: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_env(blacklist: Arc<Blacklist>) -> Self {
+        Self::new(
+            blacklist,
+            env_or("AUTO_BLACKLIST_DUMP_PERCENT", 80.0),
+            Duration::from_millis(env_or("AUTO_BLACKLIST_WINDOW", 1_800_000)),
+        )
+    }
+
+    pub fn track(&self, token: &NewToken) {
+        let mut holdings = self.holdings.lock().unwrap();
+        holdings.retain(|_, holding| holding.created_at.elapsed() < self.window);
+        holdings.entry(token.mint).or_insert(DevHolding {
+            creator: token.creator,
+            bought: 0,
+            sold: 0,
+            created_at: Instant::now(),
+        });
+    }
+
+    pub fn on_trade(&self, event: &TradeEvent) {
+        let mut holdings = self.holdings.lock().unwrap();
+        let Some(holding) = holdings.get_mut(&event.mint) else {
+            return;
+        };
+        if event.user != holding.creator {
+            return;
+        }
+
+        if event.is_buy {
+            holding.bought += event.token_amount;
+            return;
+        }
+        holding.sold += event.token_amount;
+        if holding.bought == 0
+            || (holding.sold as f64) < holding.bought as f64 * self.dump_percent / 100.0
+        {
+            return;
+        }
+
+        let creator = holding.creator;
+        holdings.remove(&event.mint);
+        drop(holdings);
+
+        let reason = format!("dumped {}", event.mint);
+        match self.blacklist.add(BlacklistKind::Creator, creator, &reason) {
+            Ok(true) => warn!("blacklisted creator {}, {}", creator, reason),
+            Ok(false) => {}
+            Err(err) => warn!("failed to blacklist creator {}: {}", creator, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_entries() {
+        let creator = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let funder = Pubkey::new_unique();
+        let contents = format!(
+            "# header\ncreator:{} # rugged\nMINT: {}\nfunder:{}\nbogus:{}\ncreator:nope\n",
+            creator, mint, funder, mint
+        );
+        let entries = BlacklistEntries::parse(&contents);
+
+        assert_eq!(entries.creators, HashSet::from([creator]));
+        assert_eq!(entries.mints, HashSet::from([mint]));
+        assert_eq!(entries.funders, HashSet::from([funder]));
+    }
+
+    #[test]
+    fn adds_and_removes_entries() {
+        let path = std::env::temp_dir().join(format!("blacklist-{}.txt", Pubkey::new_unique()));
+        let listed = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        // Hand-edited, without a trailing newline
+        std::fs::write(&path, format!("mint:{}", listed)).unwrap();
+
+        let blacklist = Blacklist::load(&path);
+        assert!(blacklist
+            .add(BlacklistKind::Creator, creator, "dumped")
+            .unwrap());
+        assert!(!blacklist.add(BlacklistKind::Creator, creator, "").unwrap());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("mint:{}\ncreator:{} # dumped\n", listed, creator)
+        );
+
+        blacklist.reload().unwrap();
+        assert!(blacklist.contains(BlacklistKind::Mint, &listed));
+        assert!(blacklist.contains(BlacklistKind::Creator, &creator));
+
+        assert!(blacklist.remove(BlacklistKind::Mint, listed).unwrap());
+        assert!(!blacklist.remove(BlacklistKind::Mint, listed).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("creator:{} # dumped\n", creator)
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
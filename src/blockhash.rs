@@ -0,0 +1,106 @@
+use crate::config::env_or;
+use crate::Result;
+use arc_swap::ArcSwapOption;
+use log::warn;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachedBlockhash {
+    pub blockhash: Hash,
+    pub last_valid_block_height: u64,
+    pub fetched_at: Instant,
+}
+
+impl CachedBlockhash {
+    pub fn age(&self) -> Duration {
+        self.fetched_at.elapsed()
+    }
+}
+
+// Latest blockhash kept fresh by a background task, so sending a snipe doesn't wait on
+// a getLatestBlockhash round-trip. Reads never take a lock.
+pub struct BlockhashCache {
+    latest: ArcSwapOption<CachedBlockhash>,
+    max_age: Duration,
+}
+
+impl BlockhashCache {
+    pub fn new(max_age: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            latest: ArcSwapOption::const_empty(),
+            max_age,
+        })
+    }
+
+    // Latest blockhash, however old it is
+    pub fn get(&self) -> Option<CachedBlockhash> {
+        self.latest.load().as_deref().copied()
+    }
+
+    // Latest blockhash, or None once it is older than the max age
+    pub fn fresh(&self) -> Option<CachedBlockhash> {
+        self.get().filter(|cached| cached.age() <= self.max_age)
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.fresh().is_none()
+    }
+
+    pub async fn refresh(&self, rpc_client: &RpcClient) -> Result<CachedBlockhash> {
+        let (blockhash, last_valid_block_height) = rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await?;
+        let cached = CachedBlockhash {
+            blockhash,
+            last_valid_block_height,
+            fetched_at: Instant::now(),
+        };
+        self.latest.store(Some(Arc::new(cached)));
+        Ok(cached)
+    }
+
+    // Cached blockhash if it is fresh, otherwise fetch one on the spot
+    pub async fn get_or_fetch(&self, rpc_client: &RpcClient) -> Result<CachedBlockhash> {
+        match self.fresh() {
+            Some(cached) => Ok(cached),
+            None => self.refresh(rpc_client).await,
+        }
+    }
+
+    // Poll for a new blockhash every `interval`. The task stops once the cache is dropped.
+    pub fn spawn_refresher(
+        self: &Arc<Self>,
+        rpc_client: Arc<RpcClient>,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let cache: Weak<Self> = Arc::downgrade(self);
+
+        tokio::spawn(async move {
+            while let Some(cache) = cache.upgrade() {
+                if let Err(err) = cache.refresh(&rpc_client).await {
+                    warn!("failed to refresh blockhash: {}", err);
+                    if cache.is_stale() {
+                        warn!("cached blockhash is stale");
+                    }
+                }
+                drop(cache);
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+
+    // Cache refreshed every BLOCKHASH_REFRESH_INTERVAL ms, stale after BLOCKHASH_MAX_AGE ms
+    pub fn spawn_from_env(rpc_client: Arc<RpcClient>) -> Arc<Self> {
+        let cache = Self::new(Duration::from_millis(env_or("BLOCKHASH_MAX_AGE", 20_000)));
+        cache.spawn_refresher(
+            rpc_client,
+            Duration::from_millis(env_or("BLOCKHASH_REFRESH_INTERVAL", 1_000)),
+        );
+        cache
+    }
+}
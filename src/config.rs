@@ -0,0 +1,31 @@
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::Keypair;
+use std::env;
+use std::str::FromStr;
+
+// Read an env var and parse it, falling back to the default when it is unset or malformed
+pub fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+// Load the trading wallet from the base58 encoded PRIVATE_KEY
+pub fn load_keypair() -> Keypair {
+    Keypair::from_base58_string(&env::var("PRIVATE_KEY").unwrap())
+}
+
+// The SDK's own conversions are deprecated in favour of string parsing, our amounts come as f64
+pub fn sol_to_lamports(sol: f64) -> u64 {
+    (sol * LAMPORTS_PER_SOL as f64) as u64
+}
+
+pub fn lamports_to_sol(lamports: u64) -> f64 {
+    lamports as f64 / LAMPORTS_PER_SOL as f64
+}
+
+// Fee paid to the warp or jito executor, CUSTOM_FEE is given in SOL
+pub fn custom_fee_lamports() -> u64 {
+    sol_to_lamports(env_or("CUSTOM_FEE", 0.001))
+}
@@ -0,0 +1,460 @@
+use crate::blacklist::{Blacklist, BlacklistKind};
+use crate::config::{env_or, lamports_to_sol, sol_to_lamports};
+use crate::events::TradeEvent;
+use crate::executor::{ExecutionResult, Executor};
+use crate::pump;
+use crate::tracker;
+use crate::Result;
+use log::{info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// How much SOL to put into a copied buy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CopySizing {
+    // Lamports per buy, whatever the leader spent
+    Fixed(u64),
+    // Share of the leader's SOL amount
+    Proportional(f64),
+}
+
+impl CopySizing {
+    pub fn lamports(&self, leader_lamports: u64) -> u64 {
+        match self {
+            Self::Fixed(lamports) => *lamports,
+            Self::Proportional(ratio) => (leader_lamports as f64 * ratio) as u64,
+        }
+    }
+}
+
+// A finite amount above zero
+fn parse_amount(s: &str) -> Option<f64> {
+    s.trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|amount| amount.is_finite() && *amount > 0.0)
+}
+
+impl CopySizing {
+    // `0.1sol` for a fixed 0.1 SOL, `25%` for a quarter of the leader's amount.
+    // Unlike FromStr the unit is required, so a number at the end of a label isn't taken for a size.
+    pub fn parse_with_unit(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if let Some(percent) = s.strip_suffix('%') {
+            return parse_amount(percent).map(|percent| Self::Proportional(percent / 100.0));
+        }
+        let sol = s
+            .len()
+            .checked_sub(3)
+            .filter(|&unit| s.is_char_boundary(unit) && s[unit..].eq_ignore_ascii_case("sol"))
+            .map(|unit| &s[..unit])?;
+        Self::fixed(sol)
+    }
+
+    fn fixed(sol: &str) -> Option<Self> {
+        let lamports = sol_to_lamports(parse_amount(sol)?);
+        (lamports > 0).then_some(Self::Fixed(lamports))
+    }
+}
+
+// `0.1` or `0.1sol` for a fixed 0.1 SOL, `25%` for a quarter of the leader's amount
+impl FromStr for CopySizing {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::parse_with_unit(s)
+            .or_else(|| Self::fixed(s))
+            .ok_or_else(|| format!("invalid copy size: {}", s.trim()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CopyConfig {
+    // For wallets without a size of their own
+    pub sizing: CopySizing,
+    // Lamports we hold at most in one mint, 0 for no cap
+    pub max_position: u64,
+    pub delay: Duration,
+    // Percent over the quoted cost we pay, and under the quoted output we accept
+    pub buy_slippage: u64,
+    pub sell_slippage: u64,
+    // Sell the share of our position the leader sold of theirs, rather than all of it
+    pub mirror_sells: bool,
+    pub max_retries: u32,
+}
+
+impl CopyConfig {
+    pub fn from_env() -> Self {
+        Self {
+            sizing: env_or("COPY_SIZE", CopySizing::Fixed(sol_to_lamports(0.01))),
+            max_position: sol_to_lamports(env_or("COPY_MAX_POSITION", 0.0)),
+            delay: Duration::from_millis(env_or("COPY_DELAY", 0)),
+            buy_slippage: env_or("BUY_SLIPPAGE", 20),
+            sell_slippage: env_or("SELL_SLIPPAGE", 20),
+            mirror_sells: env_or("COPY_MIRROR_SELLS", true),
+            max_retries: env_or("MAX_BUY_RETRIES", 10),
+        }
+    }
+}
+
+// What we hold of a mint through copied trades
+#[derive(Debug, Clone, Copy, Default)]
+struct CopyPosition {
+    token_amount: u64,
+    cost: u64,
+    // Lamports of buys in flight, counted against the cap until they land or fail
+    pending: u64,
+    // Tokens of sells in flight, no longer available to other sells until they land or fail
+    selling: u64,
+}
+
+// Mirrors the pump.fun trades of tracked wallets from our own wallet
+pub struct CopyTrader {
+    config: CopyConfig,
+    payer: Arc<Keypair>,
+    rpc_client: Arc<RpcClient>,
+    executor: Arc<Executor>,
+    blacklist: Option<Arc<Blacklist>>,
+    positions: Mutex<HashMap<Pubkey, CopyPosition>>,
+}
+
+impl CopyTrader {
+    pub fn new(
+        config: CopyConfig,
+        payer: Arc<Keypair>,
+        rpc_client: Arc<RpcClient>,
+        executor: Arc<Executor>,
+    ) -> Self {
+        Self {
+            config,
+            payer,
+            rpc_client,
+            executor,
+            blacklist: None,
+            positions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Never copy buys of blacklisted mints or creators
+    pub fn with_blacklist(mut self, blacklist: Arc<Blacklist>) -> Self {
+        self.blacklist = Some(blacklist);
+        self
+    }
+
+    // Copy the leader's trade after COPY_DELAY. `sizing` overrides COPY_SIZE for the leader and
+    // `leader_balance` is the leader's token balance after the trade.
+    pub async fn copy(
+        &self,
+        event: &TradeEvent,
+        sizing: Option<CopySizing>,
+        leader_balance: u64,
+    ) -> Result<Option<ExecutionResult>> {
+        if !self.config.delay.is_zero() {
+            tokio::time::sleep(self.config.delay).await;
+        }
+
+        if event.is_buy {
+            self.buy(event, sizing.unwrap_or(self.config.sizing)).await
+        } else {
+            // Share of the leader's holding they just sold
+            let share = event.token_amount as f64 / (leader_balance + event.token_amount) as f64;
+            self.sell(&event.mint, share).await
+        }
+    }
+
+    async fn buy(&self, event: &TradeEvent, sizing: CopySizing) -> Result<Option<ExecutionResult>> {
+        let mint = event.mint;
+        if let Some(blacklist) = &self.blacklist {
+            if blacklist.contains(BlacklistKind::Mint, &mint) {
+                info!("not copying buy of {}, the mint is blacklisted", mint);
+                return Ok(None);
+            }
+        }
+
+        // Reserved under the lock, so concurrent copies of the same mint can't overshoot the cap
+        let lamports = {
+            let mut positions = self.positions.lock().unwrap();
+            let position = positions.entry(mint).or_default();
+            let mut lamports = sizing.lamports(event.sol_amount);
+            if self.config.max_position > 0 {
+                let held = position.cost.saturating_add(position.pending);
+                lamports = lamports.min(self.config.max_position.saturating_sub(held));
+            }
+            position.pending += lamports;
+            lamports
+        };
+
+        let result = if lamports == 0 {
+            info!("not copying buy of {}, the position is at its cap", mint);
+            Ok(None)
+        } else {
+            self.execute_buy(mint, lamports).await
+        };
+
+        let mut positions = self.positions.lock().unwrap();
+        let position = positions.entry(mint).or_default();
+        position.pending -= lamports;
+        if let Ok(Some(bought)) = &result {
+            if bought.execution.confirmed {
+                position.token_amount += bought.token_amount;
+                position.cost += bought.cost;
+            }
+        }
+        if position.is_closed() {
+            positions.remove(&mint);
+        }
+        result.map(|bought| bought.map(|bought| bought.execution))
+    }
+
+    // The execution and what it bought for about `lamports`
+    async fn execute_buy(&self, mint: Pubkey, lamports: u64) -> Result<Option<CopiedBuy>> {
+        let curve = pump::fetch_bonding_curve(&self.rpc_client, &mint).await?;
+        if let (Some(blacklist), Some(creator)) = (&self.blacklist, curve.creator) {
+            if blacklist.contains(BlacklistKind::Creator, &creator) {
+                info!(
+                    "not copying buy of {}, its creator {} is blacklisted",
+                    mint, creator
+                );
+                return Ok(None);
+            }
+        }
+        let token_amount = curve.buy_quote(lamports);
+        if token_amount == 0 {
+            info!("not copying buy of {}, the bonding curve is complete", mint);
+            return Ok(None);
+        }
+        let creator = curve.fee_creator()?;
+        let max_sol_cost = lamports as u128 * (100 + self.config.buy_slippage as u128) / 100;
+        let max_sol_cost = u64::try_from(max_sol_cost).unwrap_or(u64::MAX);
+
+        info!(
+            "copying buy of {}: {} tokens for {} SOL",
+            mint,
+            token_amount,
+            lamports_to_sol(lamports)
+        );
+        let execution = self
+            .executor
+            .buy(
+                &self.payer,
+                &mint,
+                &creator,
+                token_amount,
+                max_sol_cost,
+                self.config.max_retries,
+            )
+            .await?;
+
+        if !execution.confirmed {
+            warn!(
+                "copied buy of {} did not land: {}",
+                mint,
+                execution.error.as_deref().unwrap_or("unknown error")
+            );
+            return Ok(Some(CopiedBuy {
+                execution,
+                token_amount: 0,
+                cost: 0,
+            }));
+        }
+
+        // What the buy really cost and got, the quote is only what we asked for
+        let (token_amount, cost) = match tracker::fetch_balance_change(
+            &self.rpc_client,
+            &execution.signature,
+            &self.payer.pubkey(),
+            &mint,
+        )
+        .await
+        {
+            Ok(change) if change.tokens > 0 => {
+                (change.tokens as u64, (-change.lamports).max(0) as u64)
+            }
+            Ok(_) | Err(_) => {
+                warn!(
+                    "no balance change found for the copied buy of {}, using the quote",
+                    mint
+                );
+                (token_amount, lamports)
+            }
+        };
+        Ok(Some(CopiedBuy {
+            execution,
+            token_amount,
+            cost,
+        }))
+    }
+
+    async fn sell(&self, mint: &Pubkey, share: f64) -> Result<Option<ExecutionResult>> {
+        let Some((token_amount, held)) = self.reserve_sell(mint, share) else {
+            return Ok(None);
+        };
+        let result = self.execute_sell(mint, token_amount, held).await;
+        let confirmed = matches!(&result, Ok(execution) if execution.confirmed);
+        self.settle_sell(mint, token_amount, confirmed);
+        result.map(Some)
+    }
+
+    async fn execute_sell(
+        &self,
+        mint: &Pubkey,
+        token_amount: u64,
+        held: u64,
+    ) -> Result<ExecutionResult> {
+        let curve = pump::fetch_bonding_curve(&self.rpc_client, mint).await?;
+        let creator = curve.fee_creator()?;
+        let expected = curve.sell_quote(token_amount);
+        let min_sol_output =
+            (expected as u128 * (100 - self.config.sell_slippage.min(100)) as u128 / 100) as u64;
+
+        info!(
+            "copying sell of {}: {} of our {} tokens, expecting {} SOL",
+            mint,
+            token_amount,
+            held,
+            lamports_to_sol(expected)
+        );
+        let execution = self
+            .executor
+            .sell(
+                &self.payer,
+                mint,
+                &creator,
+                token_amount,
+                min_sol_output,
+                self.config.max_retries,
+            )
+            .await?;
+
+        if !execution.confirmed {
+            warn!(
+                "copied sell of {} did not land: {}",
+                mint,
+                execution.error.as_deref().unwrap_or("unknown error")
+            );
+        }
+        Ok(execution)
+    }
+
+    // Takes the tokens to sell out of the position under the lock, so concurrent sells of the same mint
+    // can't sell them twice. Returns them with what we held before.
+    fn reserve_sell(&self, mint: &Pubkey, share: f64) -> Option<(u64, u64)> {
+        let mut positions = self.positions.lock().unwrap();
+        let position = positions.get_mut(mint)?;
+        let held = position.token_amount;
+        let token_amount = if self.config.mirror_sells && share < 1.0 {
+            ((held as f64 * share) as u64).min(held)
+        } else {
+            held
+        };
+        if token_amount == 0 {
+            return None;
+        }
+        position.token_amount -= token_amount;
+        position.selling += token_amount;
+        Some((token_amount, held))
+    }
+
+    // Gives the reserved tokens back if the sell did not land
+    fn settle_sell(&self, mint: &Pubkey, token_amount: u64, confirmed: bool) {
+        let mut positions = self.positions.lock().unwrap();
+        let Some(position) = positions.get_mut(mint) else {
+            return;
+        };
+        let held = position.token_amount + position.selling;
+        position.selling -= token_amount;
+        if confirmed {
+            // Cost stays proportional to what is left, so the cap applies to the open part
+            position.cost = (position.cost as u128 * (held - token_amount) as u128
+                / held.max(1) as u128) as u64;
+        } else {
+            position.token_amount += token_amount;
+        }
+        if position.is_closed() {
+            positions.remove(mint);
+        }
+    }
+}
+
+impl CopyPosition {
+    fn is_closed(&self) -> bool {
+        self.token_amount == 0 && self.pending == 0 && self.selling == 0
+    }
+}
+
+// A copied buy, with the tokens and lamports it took once it landed
+struct CopiedBuy {
+    execution: ExecutionResult,
+    token_amount: u64,
+    // Lamports, fees, tips and rent included
+    cost: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockhash::BlockhashCache;
+
+    fn trader(mirror_sells: bool) -> CopyTrader {
+        let rpc_client = Arc::new(RpcClient::new("http://127.0.0.1:0".to_string()));
+        let executor = Executor::from_env(rpc_client.clone(), BlockhashCache::new(Duration::ZERO));
+        CopyTrader::new(
+            CopyConfig {
+                mirror_sells,
+                ..CopyConfig::from_env()
+            },
+            Arc::new(Keypair::new()),
+            rpc_client,
+            Arc::new(executor),
+        )
+    }
+
+    fn hold(trader: &CopyTrader, mint: Pubkey, token_amount: u64, cost: u64) {
+        trader.positions.lock().unwrap().insert(
+            mint,
+            CopyPosition {
+                token_amount,
+                cost,
+                ..CopyPosition::default()
+            },
+        );
+    }
+
+    #[test]
+    fn concurrent_sells_cannot_sell_the_same_tokens() {
+        let trader = trader(true);
+        let mint = Pubkey::new_unique();
+        hold(&trader, mint, 1_000, 100);
+
+        assert_eq!(trader.reserve_sell(&mint, 0.6), Some((600, 1_000)));
+        // The leader sold the rest while our first sell is in flight
+        assert_eq!(trader.reserve_sell(&mint, 1.0), Some((400, 400)));
+        assert_eq!(trader.reserve_sell(&mint, 1.0), None);
+
+        trader.settle_sell(&mint, 400, true);
+        trader.settle_sell(&mint, 600, true);
+        assert!(trader.positions.lock().unwrap().get(&mint).is_none());
+    }
+
+    #[test]
+    fn failed_sells_give_the_tokens_back() {
+        let trader = trader(true);
+        let mint = Pubkey::new_unique();
+        hold(&trader, mint, 1_000, 100);
+
+        assert_eq!(trader.reserve_sell(&mint, 0.5), Some((500, 1_000)));
+        trader.settle_sell(&mint, 500, false);
+        assert_eq!(trader.reserve_sell(&mint, 0.5), Some((500, 1_000)));
+        trader.settle_sell(&mint, 500, true);
+
+        let positions = trader.positions.lock().unwrap();
+        let position = positions.get(&mint).unwrap();
+        assert_eq!((position.token_amount, position.selling), (500, 0));
+        assert_eq!(position.cost, 50);
+    }
+}
@@ -0,0 +1,268 @@
+use crate::config::lamports_to_sol;
+use crate::decode::Reader;
+use crate::pump::{self, FEE_BASIS_POINTS, TOKEN_DECIMALS};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_sdk::pubkey::Pubkey;
+
+const CREATE_EVENT_DISCRIMINATOR: [u8; 8] = [27, 114, 169, 77, 222, 235, 99, 118];
+const TRADE_EVENT_DISCRIMINATOR: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
+
+// Anchor emits events as base64 `Program data:` log lines. Any program can log data that decodes as a
+// pump.fun event, so only lines logged while pump.fun itself is running count.
+pub fn program_data<'a>(logs: &'a [String]) -> impl Iterator<Item = Vec<u8>> + 'a {
+    let pump = pump::PUMP_PROGRAM_ID.to_string();
+    let mut invoked: Vec<&'a str> = Vec::new();
+    logs.iter().filter_map(move |log| {
+        if let Some(data) = log.strip_prefix("Program data: ") {
+            return match invoked.last() {
+                Some(&program) if program == pump => STANDARD.decode(data.trim()).ok(),
+                _ => None,
+            };
+        }
+        let mut words = log.split(' ');
+        if let (Some("Program"), Some(program), Some(action)) =
+            (words.next(), words.next(), words.next())
+        {
+            match action {
+                "invoke" => invoked.push(program),
+                "success" | "failed:" => {
+                    invoked.pop();
+                }
+                _ => {}
+            }
+        }
+        None
+    })
+}
+
+// Emitted by pump.fun when a token is created
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateEvent {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub user: Pubkey,
+}
+
+impl CreateEvent {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.get(..8)? != CREATE_EVENT_DISCRIMINATOR {
+            return None;
+        }
+        let mut reader = Reader::new(&data[8..]);
+
+        Some(Self {
+            name: reader.string()?,
+            symbol: reader.string()?,
+            uri: reader.string()?,
+            mint: reader.pubkey()?,
+            bonding_curve: reader.pubkey()?,
+            user: reader.pubkey()?,
+        })
+    }
+
+    pub fn from_logs(logs: &[String]) -> Option<Self> {
+        program_data(logs).find_map(|data| Self::decode(&data))
+    }
+}
+
+// Emitted by pump.fun on every buy and sell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeEvent {
+    pub mint: Pubkey,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    pub user: Pubkey,
+    pub timestamp: i64,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+}
+
+impl TradeEvent {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.get(..8)? != TRADE_EVENT_DISCRIMINATOR {
+            return None;
+        }
+        let mut reader = Reader::new(&data[8..]);
+
+        Some(Self {
+            mint: reader.pubkey()?,
+            sol_amount: reader.u64()?,
+            token_amount: reader.u64()?,
+            is_buy: reader.bool()?,
+            user: reader.pubkey()?,
+            timestamp: reader.i64()?,
+            virtual_sol_reserves: reader.u64()?,
+            virtual_token_reserves: reader.u64()?,
+        })
+    }
+
+    pub fn from_logs(logs: &[String]) -> Vec<Self> {
+        program_data(logs)
+            .filter_map(|data| Self::decode(&data))
+            .collect()
+    }
+
+    pub fn sol(&self) -> f64 {
+        lamports_to_sol(self.sol_amount)
+    }
+
+    // Whole tokens
+    pub fn tokens(&self) -> f64 {
+        self.token_amount as f64 / 10f64.powi(TOKEN_DECIMALS as i32)
+    }
+
+    // Pump.fun's cut, charged on top of a buy and out of a sell
+    pub fn fee_lamports(&self) -> u64 {
+        self.sol_amount * FEE_BASIS_POINTS / 10_000
+    }
+
+    // Percentage of the bonding curve sold after the trade
+    pub fn progress(&self) -> f64 {
+        pump::bonding_curve_progress(self.virtual_token_reserves)
+    }
+
+    // SOL per whole token on the curve after the trade
+    pub fn price(&self) -> f64 {
+        if self.virtual_token_reserves == 0 {
+            return 0.0;
+        }
+        lamports_to_sol(self.virtual_sol_reserves)
+            / (self.virtual_token_reserves as f64 / 10f64.powi(TOKEN_DECIMALS as i32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(data: &mut Vec<u8>, value: &str) {
+        data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        data.extend_from_slice(value.as_bytes());
+    }
+
+    fn create_event() -> (CreateEvent, Vec<u8>) {
+        let event = CreateEvent {
+            name: "Pepe Coin".to_string(),
+            symbol: "PEPE".to_string(),
+            uri: "https://ipfs.io/ipfs/Qm".to_string(),
+            mint: Pubkey::new_unique(),
+            bonding_curve: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+        };
+        let mut data = CREATE_EVENT_DISCRIMINATOR.to_vec();
+        string(&mut data, &event.name);
+        string(&mut data, &event.symbol);
+        string(&mut data, &event.uri);
+        data.extend_from_slice(event.mint.as_ref());
+        data.extend_from_slice(event.bonding_curve.as_ref());
+        data.extend_from_slice(event.user.as_ref());
+        (event, data)
+    }
+
+    fn trade_event(is_buy: bool) -> (TradeEvent, Vec<u8>) {
+        let event = TradeEvent {
+            mint: Pubkey::new_unique(),
+            sol_amount: 1_000_000_000,
+            token_amount: 35_000_000_000_000,
+            is_buy,
+            user: Pubkey::new_unique(),
+            timestamp: 1_700_000_000,
+            virtual_sol_reserves: 31_000_000_000,
+            virtual_token_reserves: 1_038_000_000_000_000,
+        };
+        let mut data = TRADE_EVENT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(event.mint.as_ref());
+        data.extend_from_slice(&event.sol_amount.to_le_bytes());
+        data.extend_from_slice(&event.token_amount.to_le_bytes());
+        data.push(event.is_buy as u8);
+        data.extend_from_slice(event.user.as_ref());
+        data.extend_from_slice(&event.timestamp.to_le_bytes());
+        data.extend_from_slice(&event.virtual_sol_reserves.to_le_bytes());
+        data.extend_from_slice(&event.virtual_token_reserves.to_le_bytes());
+        (event, data)
+    }
+
+    fn log(data: &[u8]) -> String {
+        format!("Program data: {}", STANDARD.encode(data))
+    }
+
+    // The logs of an instruction of `program`
+    fn invoked(program: &Pubkey, logs: Vec<String>) -> Vec<String> {
+        let mut invoked = vec![format!("Program {} invoke [1]", program)];
+        invoked.extend(logs);
+        invoked.push(format!("Program {} success", program));
+        invoked
+    }
+
+    #[test]
+    fn decodes_create_events() {
+        let (event, data) = create_event();
+        assert_eq!(CreateEvent::decode(&data), Some(event.clone()));
+        assert_eq!(CreateEvent::decode(&data[..data.len() - 1]), None);
+        assert_eq!(TradeEvent::decode(&data), None);
+
+        let logs = invoked(
+            &pump::PUMP_PROGRAM_ID,
+            vec![
+                "Program log: Instruction: Create".to_string(),
+                log(&trade_event(true).1),
+                log(&data),
+            ],
+        );
+        assert_eq!(CreateEvent::from_logs(&logs), Some(event));
+    }
+
+    #[test]
+    fn decodes_trade_events() {
+        let (buy, buy_data) = trade_event(true);
+        let (sell, sell_data) = trade_event(false);
+        assert_eq!(TradeEvent::decode(&buy_data), Some(buy.clone()));
+        assert_eq!(TradeEvent::decode(&buy_data[..buy_data.len() - 1]), None);
+        assert_eq!(CreateEvent::decode(&buy_data), None);
+
+        let logs = invoked(
+            &pump::PUMP_PROGRAM_ID,
+            vec![
+                log(&create_event().1),
+                log(&buy_data),
+                "Program data: not base64!".to_string(),
+                log(&sell_data),
+            ],
+        );
+        assert_eq!(TradeEvent::from_logs(&logs), vec![buy, sell]);
+    }
+
+    #[test]
+    fn ignores_events_logged_by_other_programs() {
+        let (spoofed, spoofed_data) = trade_event(true);
+        let (real, real_data) = trade_event(true);
+        let spoofer = Pubkey::new_unique();
+
+        // Logged before any program runs, by another program, and by another program pump.fun calls
+        let mut logs = vec![log(&spoofed_data)];
+        logs.extend(invoked(&spoofer, vec![log(&spoofed_data)]));
+        let mut pump_logs = vec!["Program log: Instruction: Buy".to_string()];
+        pump_logs.extend(invoked(&spoofer, vec![log(&spoofed_data)]));
+        pump_logs.push(log(&real_data));
+        logs.extend(invoked(&pump::PUMP_PROGRAM_ID, pump_logs));
+        logs.extend(invoked(&spoofer, vec![log(&spoofed_data)]));
+
+        let events = TradeEvent::from_logs(&logs);
+        assert_eq!(events, vec![real]);
+        assert!(!events.contains(&spoofed));
+    }
+
+    #[test]
+    fn trade_amounts() {
+        let (event, _) = trade_event(true);
+        assert_eq!(event.sol(), 1.0);
+        assert_eq!(event.tokens(), 35_000_000.0);
+        assert_eq!(event.fee_lamports(), 10_000_000);
+        assert!((event.price() - 31.0 / 1_038_000_000.0).abs() < 1e-15);
+    }
+}
@@ -0,0 +1,550 @@
+use crate::blockhash::{BlockhashCache, CachedBlockhash};
+use crate::config::{custom_fee_lamports, env_or};
+use crate::fees::{ComputeBudget, PriorityFeeEstimator, MAX_COMPUTE_UNIT_LIMIT};
+use crate::jito::{BlockEngineClient, BundleBuilder, BundleOutcome};
+use crate::mint::{self, TokenProgram};
+use crate::pump;
+use crate::simulation::{self, SlippageLimits};
+use crate::tracker::{TransactionTracker, TxStatus};
+use crate::Result;
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::json;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+#[allow(deprecated)]
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const WARP_FEE_WALLET: Pubkey = pubkey!("WARPzUMPnycu9eeCZ95rcAUxorqpBqHndfV3ZP5FSyS");
+const DEFAULT_WARP_URL: &str = "https://tx.warp.id/transaction/execute";
+
+// Rent of a new token account plus the base signature fee, allowed on top of the trade in preflight
+const TOKEN_ACCOUNT_RENT: u64 = 2_039_280;
+const SIGNATURE_FEE: u64 = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutorKind {
+    Default,
+    Warp,
+    Jito,
+}
+
+impl ExecutorKind {
+    pub fn from_env() -> Self {
+        match env_or("TRANSACTION_EXECUTOR", "default".to_string())
+            .to_lowercase()
+            .as_str()
+        {
+            "jito" => Self::Jito,
+            "warp" => Self::Warp,
+            _ => Self::Default,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExecutionResult {
+    pub signature: Signature,
+    pub confirmed: bool,
+    pub attempts: u32,
+    pub latency: Duration,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Trade {
+    Buy {
+        token_amount: u64,
+        max_sol_cost: u64,
+    },
+    Sell {
+        token_amount: u64,
+        min_sol_output: u64,
+    },
+}
+
+impl Trade {
+    fn instructions(
+        &self,
+        user: &Pubkey,
+        mint: &Pubkey,
+        creator: &Pubkey,
+        token_program: &Pubkey,
+        budget: &ComputeBudget,
+    ) -> Vec<Instruction> {
+        match *self {
+            Trade::Buy {
+                token_amount,
+                max_sol_cost,
+            } => pump::buy_instructions(
+                user,
+                mint,
+                creator,
+                token_program,
+                token_amount,
+                max_sol_cost,
+                budget,
+            ),
+            Trade::Sell {
+                token_amount,
+                min_sol_output,
+            } => pump::sell_instructions(
+                user,
+                mint,
+                creator,
+                token_program,
+                token_amount,
+                min_sol_output,
+                budget,
+            ),
+        }
+    }
+
+    fn limits(&self, fee_allowance: u64) -> SlippageLimits {
+        match *self {
+            Trade::Buy {
+                token_amount,
+                max_sol_cost,
+            } => SlippageLimits::buy(
+                token_amount,
+                max_sol_cost,
+                fee_allowance + TOKEN_ACCOUNT_RENT,
+            ),
+            Trade::Sell { min_sol_output, .. } => {
+                SlippageLimits::sell(min_sol_output, fee_allowance)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct WarpResponse {
+    confirmed: bool,
+    signature: Option<String>,
+    error: Option<String>,
+}
+
+// Sends buys and sells through the executor picked by TRANSACTION_EXECUTOR.
+// The default executor pays COMPUTE_UNIT_PRICE (or the estimated fee), warp and jito pay CUSTOM_FEE.
+pub struct Executor {
+    kind: ExecutorKind,
+    rpc_client: Arc<RpcClient>,
+    blockhash_cache: Arc<BlockhashCache>,
+    tracker: TransactionTracker,
+    compute_budget: ComputeBudget,
+    fee_estimator: Option<PriorityFeeEstimator>,
+    compute_unit_margin: u64,
+    preflight: bool,
+    custom_fee: u64,
+    block_engine: BlockEngineClient,
+    http: reqwest::Client,
+    warp_url: String,
+    // Owning token program of every mint traded so far
+    token_programs: Mutex<HashMap<Pubkey, TokenProgram>>,
+}
+
+impl Executor {
+    pub fn from_env(rpc_client: Arc<RpcClient>, blockhash_cache: Arc<BlockhashCache>) -> Self {
+        let fee_estimator = env_or("DYNAMIC_PRIORITY_FEE", false)
+            .then(|| PriorityFeeEstimator::from_env(rpc_client.clone()));
+
+        Self {
+            kind: ExecutorKind::from_env(),
+            tracker: TransactionTracker::new(rpc_client.clone())
+                .with_blockhash_cache(blockhash_cache.clone()),
+            rpc_client,
+            blockhash_cache,
+            compute_budget: ComputeBudget::from_env(),
+            fee_estimator,
+            compute_unit_margin: env_or("COMPUTE_UNIT_MARGIN", 20),
+            preflight: simulation::preflight_enabled(),
+            custom_fee: custom_fee_lamports(),
+            block_engine: BlockEngineClient::from_env(),
+            http: reqwest::Client::new(),
+            warp_url: env_or("WARP_URL", DEFAULT_WARP_URL.to_string()),
+            token_programs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn kind(&self) -> ExecutorKind {
+        self.kind
+    }
+
+    // Token-2022 mints need their own program in the token accounts and pump instructions
+    async fn token_program(&self, mint: &Pubkey) -> Result<Pubkey> {
+        if let Some(program) = self.token_programs.lock().unwrap().get(mint) {
+            return Ok(program.id());
+        }
+        let program = mint::fetch_token_program(&self.rpc_client, mint).await?;
+        self.token_programs.lock().unwrap().insert(*mint, program);
+        Ok(program.id())
+    }
+
+    // `creator` is the curve's creator, see `BondingCurve::fee_creator`
+    pub async fn buy(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        creator: &Pubkey,
+        token_amount: u64,
+        max_sol_cost: u64,
+        max_retries: u32,
+    ) -> Result<ExecutionResult> {
+        let trade = Trade::Buy {
+            token_amount,
+            max_sol_cost,
+        };
+        self.execute(payer, mint, creator, trade, max_retries).await
+    }
+
+    // `creator` is the curve's creator, see `BondingCurve::fee_creator`
+    pub async fn sell(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        creator: &Pubkey,
+        token_amount: u64,
+        min_sol_output: u64,
+        max_retries: u32,
+    ) -> Result<ExecutionResult> {
+        let trade = Trade::Sell {
+            token_amount,
+            min_sol_output,
+        };
+        self.execute(payer, mint, creator, trade, max_retries).await
+    }
+
+    async fn execute(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        creator: &Pubkey,
+        trade: Trade,
+        max_retries: u32,
+    ) -> Result<ExecutionResult> {
+        let token_program = self.token_program(mint).await?;
+        let budget = self
+            .compute_budget(payer, mint, creator, &token_program, trade)
+            .await?;
+        let instructions =
+            trade.instructions(&payer.pubkey(), mint, creator, &token_program, &budget);
+
+        match self.kind {
+            ExecutorKind::Default => {
+                self.execute_default(payer, &instructions, max_retries)
+                    .await
+            }
+            ExecutorKind::Warp => self.execute_warp(payer, &instructions, max_retries).await,
+            ExecutorKind::Jito => self.execute_jito(payer, &instructions, max_retries).await,
+        }
+    }
+
+    // Unit price from the estimator for the default executor, then the unit limit sized by preflight
+    async fn compute_budget(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        creator: &Pubkey,
+        token_program: &Pubkey,
+        trade: Trade,
+    ) -> Result<ComputeBudget> {
+        let mut budget = match (self.kind, &self.fee_estimator) {
+            (ExecutorKind::Default, Some(estimator)) => {
+                estimator.compute_budget(mint, self.compute_budget).await
+            }
+            (ExecutorKind::Default, None) => self.compute_budget,
+            _ => self.compute_budget.with_unit_price(0),
+        };
+
+        if self.preflight {
+            let user = payer.pubkey();
+            let simulated = ComputeBudget {
+                unit_limit: MAX_COMPUTE_UNIT_LIMIT,
+                ..budget
+            };
+            let blockhash = self
+                .blockhash_cache
+                .get_or_fetch(&self.rpc_client)
+                .await?
+                .blockhash;
+            let transaction = Transaction::new_signed_with_payer(
+                &trade.instructions(&user, mint, creator, token_program, &simulated),
+                Some(&user),
+                &[payer],
+                blockhash,
+            );
+            let limits = trade.limits(SIGNATURE_FEE + simulated.priority_fee_lamports());
+            let report = simulation::preflight(
+                &self.rpc_client,
+                &transaction,
+                &user,
+                mint,
+                token_program,
+                limits,
+            )
+            .await?;
+            if let Some(units_consumed) = report.units_consumed {
+                budget = budget.sized_from_simulation(units_consumed, self.compute_unit_margin);
+            }
+        }
+
+        Ok(budget)
+    }
+
+    // A blockhash valid beyond `expired_block_height`, so a retry never reuses the one that just expired
+    async fn latest_blockhash(&self, expired_block_height: u64) -> Result<CachedBlockhash> {
+        let cached = self.blockhash_cache.get_or_fetch(&self.rpc_client).await?;
+        if cached.last_valid_block_height > expired_block_height {
+            return Ok(cached);
+        }
+        self.blockhash_cache.refresh(&self.rpc_client).await
+    }
+
+    // Wait until a transaction that didn't confirm can no longer land, before it is re-signed with a
+    // new blockhash. Otherwise both could land and the trade fill twice.
+    async fn settle(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+    ) -> Option<TxStatus> {
+        info!("waiting for {} to land or expire", signature);
+        self.tracker
+            .wait_until_settled(signature, last_valid_block_height)
+            .await
+    }
+
+    async fn execute_default(
+        &self,
+        payer: &Keypair,
+        instructions: &[Instruction],
+        max_retries: u32,
+    ) -> Result<ExecutionResult> {
+        let tracked = self
+            .tracker
+            .send_and_confirm(
+                |blockhash| {
+                    Transaction::new_signed_with_payer(
+                        instructions,
+                        Some(&payer.pubkey()),
+                        &[payer],
+                        blockhash,
+                    )
+                },
+                max_retries,
+            )
+            .await?;
+
+        let error = match &tracked.status {
+            TxStatus::Confirmed { .. } => None,
+            TxStatus::Failed { error, .. } => Some(error.to_string()),
+            TxStatus::Expired => Some("blockhash expired on every attempt".to_string()),
+        };
+        Ok(ExecutionResult {
+            signature: tracked.signature,
+            confirmed: tracked.is_confirmed(),
+            attempts: tracked.attempts,
+            latency: tracked.latency,
+            error,
+        })
+    }
+
+    async fn execute_jito(
+        &self,
+        payer: &Keypair,
+        instructions: &[Instruction],
+        max_retries: u32,
+    ) -> Result<ExecutionResult> {
+        let started = Instant::now();
+        let mut signature = Signature::default();
+        let mut error = None;
+        let mut attempts = 0;
+        let mut expired_block_height = 0;
+
+        while attempts < max_retries.max(1) {
+            attempts += 1;
+            let latest = self.latest_blockhash(expired_block_height).await?;
+            let bundle = BundleBuilder::new(payer, latest.blockhash)
+                .add_transaction(instructions.to_vec())
+                .build(self.custom_fee)?;
+            signature = bundle[0].signatures[0];
+
+            // A rejected bundle is not in flight, so it can be re-signed right away
+            let bundle_id = match self.block_engine.send_bundle(&bundle).await {
+                Ok(bundle_id) => bundle_id,
+                Err(err) => {
+                    warn!("attempt {} failed to send bundle: {}", attempts, err);
+                    error = Some(err.to_string());
+                    continue;
+                }
+            };
+
+            match self
+                .block_engine
+                .wait_for_bundle(
+                    &bundle_id,
+                    Duration::from_millis(500),
+                    Duration::from_secs(30),
+                )
+                .await
+            {
+                Ok(BundleOutcome::Landed { slot }) => {
+                    info!("bundle {} landed in slot {}", bundle_id, slot);
+                    return Ok(ExecutionResult {
+                        signature,
+                        confirmed: true,
+                        attempts,
+                        latency: started.elapsed(),
+                        error: None,
+                    });
+                }
+                Ok(outcome) => {
+                    warn!("bundle {} did not land: {:?}", bundle_id, outcome);
+                    error = Some(format!("bundle {:?}", outcome));
+                }
+                // Unknown like a timeout, the bundle may still land
+                Err(err) => {
+                    warn!("failed to poll bundle {}: {}", bundle_id, err);
+                    error = Some(err.to_string());
+                }
+            }
+
+            match self
+                .settle(&signature, latest.last_valid_block_height)
+                .await
+            {
+                Some(TxStatus::Confirmed { .. }) => {
+                    return Ok(ExecutionResult {
+                        signature,
+                        confirmed: true,
+                        attempts,
+                        latency: started.elapsed(),
+                        error: None,
+                    })
+                }
+                Some(TxStatus::Failed { error: failed, .. }) => error = Some(failed.to_string()),
+                _ => {}
+            }
+            expired_block_height = latest.last_valid_block_height;
+        }
+
+        Ok(ExecutionResult {
+            signature,
+            confirmed: false,
+            attempts,
+            latency: started.elapsed(),
+            error,
+        })
+    }
+
+    // Warp takes the fee as a separate transfer sent alongside our transaction
+    async fn execute_warp(
+        &self,
+        payer: &Keypair,
+        instructions: &[Instruction],
+        max_retries: u32,
+    ) -> Result<ExecutionResult> {
+        let started = Instant::now();
+        let user = payer.pubkey();
+        let mut signature = Signature::default();
+        let mut error = None;
+        let mut attempts = 0;
+        let mut expired_block_height = 0;
+
+        while attempts < max_retries.max(1) {
+            attempts += 1;
+            let latest = self.latest_blockhash(expired_block_height).await?;
+            let fee_transaction = Transaction::new_signed_with_payer(
+                &[system_instruction::transfer(
+                    &user,
+                    &WARP_FEE_WALLET,
+                    self.custom_fee,
+                )],
+                Some(&user),
+                &[payer],
+                latest.blockhash,
+            );
+            let transaction = Transaction::new_signed_with_payer(
+                instructions,
+                Some(&user),
+                &[payer],
+                latest.blockhash,
+            );
+            signature = transaction.signatures[0];
+
+            let request = json!({
+                "transactions": [
+                    bs58::encode(bincode::serialize(&fee_transaction)?).into_string(),
+                    bs58::encode(bincode::serialize(&transaction)?).into_string(),
+                ],
+                "latestBlockhash": {
+                    "blockhash": latest.blockhash.to_string(),
+                    "lastValidBlockHeight": latest.last_valid_block_height,
+                },
+            });
+            let response = match self.http.post(&self.warp_url).json(&request).send().await {
+                Ok(response) => response.json::<WarpResponse>().await,
+                Err(err) => Err(err),
+            };
+
+            match response {
+                Ok(response) if response.confirmed => {
+                    return Ok(ExecutionResult {
+                        signature: response
+                            .signature
+                            .and_then(|signature| signature.parse().ok())
+                            .unwrap_or(signature),
+                        confirmed: true,
+                        attempts,
+                        latency: started.elapsed(),
+                        error: None,
+                    })
+                }
+                Ok(response) => {
+                    warn!(
+                        "warp attempt {} not confirmed: {:?}",
+                        attempts, response.error
+                    );
+                    error = response.error;
+                }
+                Err(err) => {
+                    warn!("warp attempt {} failed: {}", attempts, err);
+                    error = Some(err.to_string());
+                }
+            }
+
+            // Warp may still land it, or the response was lost on the way back
+            match self
+                .settle(&signature, latest.last_valid_block_height)
+                .await
+            {
+                Some(TxStatus::Confirmed { .. }) => {
+                    return Ok(ExecutionResult {
+                        signature,
+                        confirmed: true,
+                        attempts,
+                        latency: started.elapsed(),
+                        error: None,
+                    })
+                }
+                Some(TxStatus::Failed { error: failed, .. }) => error = Some(failed.to_string()),
+                _ => {}
+            }
+            expired_block_height = latest.last_valid_block_height;
+        }
+
+        Ok(ExecutionResult {
+            signature,
+            confirmed: false,
+            attempts,
+            latency: started.elapsed(),
+            error,
+        })
+    }
+}
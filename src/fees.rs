@@ -0,0 +1,210 @@
+use crate::config::env_or;
+use crate::pump;
+use crate::Result;
+use log::warn;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+use std::sync::Arc;
+
+// Highest compute unit limit a transaction can request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudget {
+    pub unit_limit: u32,
+    // Priority fee in micro-lamports per compute unit
+    pub unit_price: u64,
+}
+
+impl ComputeBudget {
+    // Static budget from COMPUTE_UNIT_LIMIT and COMPUTE_UNIT_PRICE
+    pub fn from_env() -> Self {
+        Self {
+            unit_limit: env_or("COMPUTE_UNIT_LIMIT", 101_337),
+            unit_price: env_or("COMPUTE_UNIT_PRICE", 421_197),
+        }
+    }
+
+    pub fn instructions(&self) -> Vec<Instruction> {
+        vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(self.unit_limit),
+            ComputeBudgetInstruction::set_compute_unit_price(self.unit_price),
+        ]
+    }
+
+    // Total priority fee paid if the whole unit limit is requested
+    pub fn priority_fee_lamports(&self) -> u64 {
+        (self.unit_limit as u128 * self.unit_price as u128 / 1_000_000) as u64
+    }
+
+    pub fn with_unit_price(self, unit_price: u64) -> Self {
+        Self { unit_price, ..self }
+    }
+
+    // Size the unit limit to what simulation consumed plus a margin in percent
+    pub fn sized_from_simulation(self, units_consumed: u64, margin_percent: u64) -> Self {
+        Self {
+            unit_limit: compute_unit_limit(units_consumed, margin_percent),
+            ..self
+        }
+    }
+}
+
+pub fn compute_unit_limit(units_consumed: u64, margin_percent: u64) -> u32 {
+    let limit = units_consumed.saturating_mul(100 + margin_percent) / 100;
+    limit.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+// Nearest-rank percentile, `percentile` in 0..=100. Sorts `values` in place.
+pub fn percentile(values: &mut [u64], percentile: f64) -> u64 {
+    if values.is_empty() {
+        return 0;
+    }
+    values.sort_unstable();
+    let rank = (percentile.clamp(0.0, 100.0) / 100.0 * values.len() as f64).ceil() as usize;
+    values[rank.saturating_sub(1).min(values.len() - 1)]
+}
+
+// Estimates the unit price from recent prioritization fees paid on the accounts we are about to write
+pub struct PriorityFeeEstimator {
+    rpc_client: Arc<RpcClient>,
+    percentile: f64,
+    min_unit_price: u64,
+    max_unit_price: u64,
+}
+
+impl PriorityFeeEstimator {
+    pub fn new(
+        rpc_client: Arc<RpcClient>,
+        percentile: f64,
+        min_unit_price: u64,
+        max_unit_price: u64,
+    ) -> Self {
+        Self {
+            rpc_client,
+            percentile,
+            min_unit_price,
+            max_unit_price,
+        }
+    }
+
+    // PRIORITY_FEE_PERCENTILE, PRIORITY_FEE_MIN and PRIORITY_FEE_MAX (micro-lamports per unit)
+    pub fn from_env(rpc_client: Arc<RpcClient>) -> Self {
+        Self::new(
+            rpc_client,
+            env_or("PRIORITY_FEE_PERCENTILE", 75.0),
+            env_or("PRIORITY_FEE_MIN", 0),
+            env_or("PRIORITY_FEE_MAX", 2_000_000),
+        )
+    }
+
+    // Per-slot fees recently paid by transactions writing any of the accounts
+    pub async fn sample(&self, accounts: &[Pubkey]) -> Result<Vec<u64>> {
+        let fees = self
+            .rpc_client
+            .get_recent_prioritization_fees(accounts)
+            .await?;
+        Ok(fees.iter().map(|fee| fee.prioritization_fee).collect())
+    }
+
+    pub async fn estimate(&self, accounts: &[Pubkey]) -> Result<u64> {
+        let mut fees = self.sample(accounts).await?;
+        let estimate = percentile(&mut fees, self.percentile);
+        Ok(estimate.clamp(
+            self.min_unit_price,
+            self.max_unit_price.max(self.min_unit_price),
+        ))
+    }
+
+    // Estimate for trading a mint, sampling the pump program and the mint's bonding curve
+    pub async fn estimate_for_mint(&self, mint: &Pubkey) -> Result<u64> {
+        self.estimate(&[pump::PUMP_PROGRAM_ID, pump::bonding_curve_address(mint)])
+            .await
+    }
+
+    // `fallback` with its unit price replaced by the estimate, or unchanged when estimation fails
+    pub async fn compute_budget(&self, mint: &Pubkey, fallback: ComputeBudget) -> ComputeBudget {
+        match self.estimate_for_mint(mint).await {
+            Ok(unit_price) => fallback.with_unit_price(unit_price),
+            Err(err) => {
+                warn!("priority fee estimation failed, using static fee: {}", err);
+                fallback
+            }
+        }
+    }
+}
+
+// Simulate the transaction and size its compute unit limit from the units it consumed
+pub async fn simulate_compute_unit_limit(
+    rpc_client: &RpcClient,
+    transaction: &Transaction,
+    margin_percent: u64,
+) -> Result<u32> {
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let result = rpc_client
+        .simulate_transaction_with_config(transaction, config)
+        .await?
+        .value;
+
+    if let Some(err) = result.err {
+        return Err(format!("simulation failed: {}", err).into());
+    }
+    let units_consumed = result
+        .units_consumed
+        .ok_or("simulation did not report units consumed")?;
+    Ok(compute_unit_limit(units_consumed, margin_percent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_uses_the_nearest_rank() {
+        let mut fees = vec![50, 10, 40, 20, 30];
+        assert_eq!(percentile(&mut fees, 0.0), 10);
+        assert_eq!(percentile(&mut fees, 20.0), 10);
+        assert_eq!(percentile(&mut fees, 50.0), 30);
+        assert_eq!(percentile(&mut fees, 75.0), 40);
+        assert_eq!(percentile(&mut fees, 100.0), 50);
+        assert_eq!(fees, vec![10, 20, 30, 40, 50]);
+    }
+
+    #[test]
+    fn percentile_clamps_out_of_range_input() {
+        assert_eq!(percentile(&mut [], 75.0), 0);
+        assert_eq!(percentile(&mut [7], 75.0), 7);
+        assert_eq!(percentile(&mut [1, 2, 3], -10.0), 1);
+        assert_eq!(percentile(&mut [1, 2, 3], 250.0), 3);
+    }
+
+    #[test]
+    fn unit_limit_adds_the_margin_up_to_the_maximum() {
+        assert_eq!(compute_unit_limit(50_000, 20), 60_000);
+        assert_eq!(compute_unit_limit(50_000, 0), 50_000);
+        assert_eq!(compute_unit_limit(1_300_000, 20), MAX_COMPUTE_UNIT_LIMIT);
+        assert_eq!(compute_unit_limit(u64::MAX, 20), MAX_COMPUTE_UNIT_LIMIT);
+    }
+
+    #[test]
+    fn priority_fee_is_price_times_limit_in_lamports() {
+        let budget = ComputeBudget {
+            unit_limit: 200_000,
+            unit_price: 500_000,
+        };
+        assert_eq!(budget.priority_fee_lamports(), 100_000);
+        assert_eq!(budget.with_unit_price(0).priority_fee_lamports(), 0);
+        assert_eq!(
+            budget.sized_from_simulation(100_000, 10).unit_limit,
+            110_000
+        );
+    }
+}
@@ -0,0 +1,23 @@
+use super::{Filter, FilterContext, FilterResult};
+
+// CHECK_IF_COPYCAT: the name, symbol and image must not copy a recent launch
+pub struct CopycatFilter;
+
+impl Filter for CopycatFilter {
+    fn name(&self) -> &'static str {
+        "copycat"
+    }
+
+    fn needs_offchain(&self) -> bool {
+        true
+    }
+
+    fn check(&self, context: &FilterContext) -> FilterResult {
+        let copycats = &context.token.annotations.copycats;
+        if copycats.is_empty() {
+            return FilterResult::pass(self.name(), "no recent launch copied");
+        }
+        let reasons: Vec<String> = copycats.iter().map(ToString::to_string).collect();
+        FilterResult::fail(self.name(), reasons.join(", "))
+    }
+}
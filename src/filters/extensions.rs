@@ -0,0 +1,24 @@
+use super::{Filter, FilterContext, FilterResult};
+
+// CHECK_IF_RISKY_EXTENSIONS: no Token-2022 transfer fee, transfer hook, permanent delegate
+// or mint close authority
+pub struct ExtensionsFilter;
+
+impl Filter for ExtensionsFilter {
+    fn name(&self) -> &'static str {
+        "extensions"
+    }
+
+    fn check(&self, context: &FilterContext) -> FilterResult {
+        let Some(mint) = &context.mint else {
+            return FilterResult::fail(self.name(), "mint account not found");
+        };
+
+        let risky: Vec<String> = mint.risky_extensions().map(ToString::to_string).collect();
+        if risky.is_empty() {
+            FilterResult::pass(self.name(), format!("no risky {} extensions", mint.program))
+        } else {
+            FilterResult::fail(self.name(), risky.join(", "))
+        }
+    }
+}
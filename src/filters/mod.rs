@@ -0,0 +1,216 @@
+use crate::config::env_or;
+use crate::launch::NewToken;
+use crate::metadata::{self, Metadata};
+use crate::mint::MintInfo;
+use crate::pump::BondingCurve;
+use crate::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+
+mod copycat;
+mod extensions;
+mod pool_size;
+mod safety;
+mod scheduler;
+mod socials;
+mod text;
+
+pub use copycat::CopycatFilter;
+pub use extensions::ExtensionsFilter;
+pub use pool_size::PoolSizeFilter;
+pub use safety::{BurnedFilter, FreezableFilter, MutableFilter, RenouncedFilter};
+pub use scheduler::{FilterDecision, FilterSchedule, FilterScheduler};
+pub use socials::{SocialsFilter, TwitterReuseFilter};
+pub use text::{TextField, TextFilter};
+
+// Everything the filters look at for one token, fetched once per evaluation
+#[derive(Debug, Clone)]
+pub struct FilterContext {
+    pub token: NewToken,
+    pub mint: Option<MintInfo>,
+    pub metadata: Option<Metadata>,
+    pub bonding_curve: Option<BondingCurve>,
+}
+
+impl FilterContext {
+    pub async fn fetch(rpc_client: &RpcClient, token: &NewToken) -> Result<Self> {
+        let accounts = rpc_client
+            .get_multiple_accounts(&[
+                token.mint,
+                metadata::metadata_address(&token.mint),
+                token.bonding_curve,
+            ])
+            .await?;
+
+        Ok(Self {
+            token: token.clone(),
+            mint: accounts[0]
+                .as_ref()
+                .and_then(|account| MintInfo::unpack(&account.owner, &account.data)),
+            metadata: accounts[1]
+                .as_ref()
+                .and_then(|account| Metadata::decode(&account.data))
+                .or_else(|| token.metadata.clone()),
+            bonding_curve: accounts[2]
+                .as_ref()
+                .and_then(|account| BondingCurve::decode(&account.data)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterResult {
+    pub filter: &'static str,
+    pub passed: bool,
+    pub reason: String,
+}
+
+impl FilterResult {
+    pub fn pass(filter: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            filter,
+            passed: true,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn fail(filter: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            filter,
+            passed: false,
+            reason: reason.into(),
+        }
+    }
+}
+
+pub trait Filter: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn check(&self, context: &FilterContext) -> FilterResult;
+
+    // Whether the check reads the off-chain metadata or the annotations built from it
+    fn needs_offchain(&self) -> bool {
+        false
+    }
+}
+
+// Outcome of every filter for one token
+#[derive(Debug, Clone)]
+pub struct FilterReport {
+    pub mint: Pubkey,
+    pub results: Vec<FilterResult>,
+}
+
+impl FilterReport {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|result| result.passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &FilterResult> {
+        self.results.iter().filter(|result| !result.passed)
+    }
+}
+
+impl fmt::Display for FilterReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.mint,
+            if self.passed() {
+                "passed filters"
+            } else {
+                "failed filters"
+            }
+        )?;
+        for result in &self.results {
+            write!(
+                f,
+                "\n  [{}] {}: {}",
+                if result.passed { "PASS" } else { "FAIL" },
+                result.filter,
+                result.reason
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct FilterPipeline {
+    filters: Vec<Box<dyn Filter>>,
+}
+
+impl FilterPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, filter: impl Filter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    // Filters switched on by the CHECK_IF_*, pool size, REQUIRE_* and text pattern settings
+    pub fn from_env() -> Self {
+        let mut pipeline = Self::new();
+        if env_or("CHECK_IF_MUTABLE", false) {
+            pipeline = pipeline.with(MutableFilter);
+        }
+        if env_or("CHECK_IF_MINT_IS_RENOUNCED", false) {
+            pipeline = pipeline.with(RenouncedFilter);
+        }
+        if env_or("CHECK_IF_FREEZABLE", false) {
+            pipeline = pipeline.with(FreezableFilter);
+        }
+        if env_or("CHECK_IF_BURNED", false) {
+            pipeline = pipeline.with(BurnedFilter);
+        }
+        if env_or("CHECK_IF_RISKY_EXTENSIONS", false) {
+            pipeline = pipeline.with(ExtensionsFilter);
+        }
+        let pool_size = PoolSizeFilter::from_env();
+        if pool_size.is_enabled() {
+            pipeline = pipeline.with(pool_size);
+        }
+        let socials = SocialsFilter::from_env();
+        if socials.is_enabled() {
+            pipeline = pipeline.with(socials);
+        }
+        if env_or("CHECK_IF_TWITTER_REUSED", false) {
+            pipeline = pipeline.with(TwitterReuseFilter);
+        }
+        if env_or("CHECK_IF_COPYCAT", false) {
+            pipeline = pipeline.with(CopycatFilter);
+        }
+        for text in TextFilter::from_env() {
+            pipeline = pipeline.with(text);
+        }
+        pipeline
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    pub fn needs_offchain(&self) -> bool {
+        self.filters.iter().any(|filter| filter.needs_offchain())
+    }
+
+    pub fn evaluate(&self, context: &FilterContext) -> FilterReport {
+        FilterReport {
+            mint: context.token.mint,
+            results: self
+                .filters
+                .iter()
+                .map(|filter| filter.check(context))
+                .collect(),
+        }
+    }
+
+    pub async fn run(&self, rpc_client: &RpcClient, token: &NewToken) -> Result<FilterReport> {
+        let context = FilterContext::fetch(rpc_client, token).await?;
+        Ok(self.evaluate(&context))
+    }
+}
@@ -0,0 +1,153 @@
+use super::{Filter, FilterContext, FilterResult};
+use crate::config::{env_or, lamports_to_sol};
+
+// MIN_POOL_SIZE / MAX_POOL_SIZE on the SOL deposited in the bonding curve and
+// MIN_MARKET_CAP / MAX_MARKET_CAP on its market cap, all in SOL, 0 disables a bound
+pub struct PoolSizeFilter {
+    pub min_pool_size: f64,
+    pub max_pool_size: f64,
+    pub min_market_cap: f64,
+    pub max_market_cap: f64,
+}
+
+impl PoolSizeFilter {
+    pub fn from_env() -> Self {
+        Self {
+            min_pool_size: env_or("MIN_POOL_SIZE", 0.0),
+            max_pool_size: env_or("MAX_POOL_SIZE", 0.0),
+            min_market_cap: env_or("MIN_MARKET_CAP", 0.0),
+            max_market_cap: env_or("MAX_MARKET_CAP", 0.0),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        [
+            self.min_pool_size,
+            self.max_pool_size,
+            self.min_market_cap,
+            self.max_market_cap,
+        ]
+        .iter()
+        .any(|bound| *bound > 0.0)
+    }
+}
+
+fn out_of_range(value: f64, min: f64, max: f64) -> bool {
+    (min > 0.0 && value < min) || (max > 0.0 && value > max)
+}
+
+impl Filter for PoolSizeFilter {
+    fn name(&self) -> &'static str {
+        "pool size"
+    }
+
+    fn check(&self, context: &FilterContext) -> FilterResult {
+        let Some(curve) = &context.bonding_curve else {
+            return FilterResult::fail(self.name(), "bonding curve not found");
+        };
+
+        let pool_size = lamports_to_sol(curve.real_sol_reserves);
+        let market_cap = curve.market_cap_sol();
+        let values = format!(
+            "pool {:.3} SOL, market cap {:.3} SOL",
+            pool_size, market_cap
+        );
+
+        if out_of_range(pool_size, self.min_pool_size, self.max_pool_size) {
+            FilterResult::fail(
+                self.name(),
+                format!(
+                    "{}, pool outside {}..{} SOL",
+                    values, self.min_pool_size, self.max_pool_size
+                ),
+            )
+        } else if out_of_range(market_cap, self.min_market_cap, self.max_market_cap) {
+            FilterResult::fail(
+                self.name(),
+                format!(
+                    "{}, market cap outside {}..{} SOL",
+                    values, self.min_market_cap, self.max_market_cap
+                ),
+            )
+        } else {
+            FilterResult::pass(self.name(), values)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::launch::NewToken;
+    use crate::pump::BondingCurve;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn context(real_sol_reserves: u64) -> FilterContext {
+        FilterContext {
+            token: NewToken::new(String::new(), Pubkey::new_unique(), Pubkey::new_unique()),
+            mint: None,
+            metadata: None,
+            // Fresh pump curve, roughly 28 SOL market cap
+            bonding_curve: Some(BondingCurve {
+                virtual_token_reserves: 1_073_000_000_000_000,
+                virtual_sol_reserves: 30_000_000_000,
+                real_token_reserves: 793_100_000_000_000,
+                real_sol_reserves,
+                token_total_supply: 1_000_000_000_000_000,
+                complete: false,
+                creator: None,
+            }),
+        }
+    }
+
+    fn filter(min_pool: f64, max_pool: f64, min_cap: f64, max_cap: f64) -> PoolSizeFilter {
+        PoolSizeFilter {
+            min_pool_size: min_pool,
+            max_pool_size: max_pool,
+            min_market_cap: min_cap,
+            max_market_cap: max_cap,
+        }
+    }
+
+    #[test]
+    fn zero_disables_bounds() {
+        let filter = filter(0.0, 0.0, 0.0, 0.0);
+        assert!(!filter.is_enabled());
+        assert!(filter.check(&context(0)).passed);
+    }
+
+    #[test]
+    fn checks_the_pool_range() {
+        let filter = filter(1.0, 10.0, 0.0, 0.0);
+        assert!(filter.is_enabled());
+        assert!(!filter.check(&context(500_000_000)).passed);
+        assert!(filter.check(&context(5_000_000_000)).passed);
+        let result = filter.check(&context(20_000_000_000));
+        assert!(!result.passed);
+        assert!(result.reason.contains("pool outside"), "{}", result.reason);
+    }
+
+    #[test]
+    fn checks_the_market_cap_range() {
+        let context = context(0);
+        let market_cap = context.bonding_curve.as_ref().unwrap().market_cap_sol();
+        assert!((market_cap - 27.96).abs() < 0.01, "{}", market_cap);
+
+        assert!(filter(0.0, 0.0, 20.0, 40.0).check(&context).passed);
+        assert!(!filter(0.0, 0.0, 30.0, 0.0).check(&context).passed);
+        let result = filter(0.0, 0.0, 0.0, 25.0).check(&context);
+        assert!(!result.passed);
+        assert!(
+            result.reason.contains("market cap outside"),
+            "{}",
+            result.reason
+        );
+    }
+
+    #[test]
+    fn fails_without_a_bonding_curve() {
+        let mut context = context(0);
+        context.bonding_curve = None;
+        assert!(!filter(0.0, 0.0, 0.0, 0.0).check(&context).passed);
+    }
+}
@@ -0,0 +1,89 @@
+use super::{Filter, FilterContext, FilterResult};
+
+// CHECK_IF_MUTABLE: metadata must be immutable
+pub struct MutableFilter;
+
+impl Filter for MutableFilter {
+    fn name(&self) -> &'static str {
+        "mutable"
+    }
+
+    fn check(&self, context: &FilterContext) -> FilterResult {
+        match &context.metadata {
+            None => FilterResult::fail(self.name(), "metadata account not found"),
+            Some(metadata) if metadata.is_mutable => FilterResult::fail(
+                self.name(),
+                format!("metadata is mutable by {}", metadata.update_authority),
+            ),
+            Some(_) => FilterResult::pass(self.name(), "metadata is immutable"),
+        }
+    }
+}
+
+// CHECK_IF_MINT_IS_RENOUNCED: nobody may mint more supply
+pub struct RenouncedFilter;
+
+impl Filter for RenouncedFilter {
+    fn name(&self) -> &'static str {
+        "renounced"
+    }
+
+    fn check(&self, context: &FilterContext) -> FilterResult {
+        match &context.mint {
+            None => FilterResult::fail(self.name(), "mint account not found"),
+            Some(mint) => match mint.mint_authority {
+                Some(authority) => {
+                    FilterResult::fail(self.name(), format!("mint authority is {}", authority))
+                }
+                None => FilterResult::pass(self.name(), "mint authority renounced"),
+            },
+        }
+    }
+}
+
+// CHECK_IF_FREEZABLE: nobody may freeze our token account
+pub struct FreezableFilter;
+
+impl Filter for FreezableFilter {
+    fn name(&self) -> &'static str {
+        "freezable"
+    }
+
+    fn check(&self, context: &FilterContext) -> FilterResult {
+        match &context.mint {
+            None => FilterResult::fail(self.name(), "mint account not found"),
+            Some(mint) => match mint.freeze_authority {
+                Some(authority) => {
+                    FilterResult::fail(self.name(), format!("freeze authority is {}", authority))
+                }
+                None => FilterResult::pass(self.name(), "no freeze authority"),
+            },
+        }
+    }
+}
+
+// CHECK_IF_BURNED: pump.fun has no LP tokens to burn, liquidity stays locked in the bonding curve
+// until it completes, so the curve must exist and still be trading
+pub struct BurnedFilter;
+
+impl Filter for BurnedFilter {
+    fn name(&self) -> &'static str {
+        "burned"
+    }
+
+    fn check(&self, context: &FilterContext) -> FilterResult {
+        match &context.bonding_curve {
+            None => FilterResult::fail(self.name(), "bonding curve not found"),
+            Some(curve) if curve.complete => {
+                FilterResult::fail(self.name(), "bonding curve complete, liquidity migrated")
+            }
+            Some(curve) => FilterResult::pass(
+                self.name(),
+                format!(
+                    "liquidity locked in bonding curve ({} lamports)",
+                    curve.real_sol_reserves
+                ),
+            ),
+        }
+    }
+}
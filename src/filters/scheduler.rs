@@ -0,0 +1,117 @@
+use super::{FilterPipeline, FilterReport};
+use crate::config::env_or;
+use crate::launch::NewToken;
+use log::{debug, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy)]
+pub struct FilterSchedule {
+    pub interval: Duration,
+    pub duration: Duration,
+    pub consecutive_matches: u32,
+}
+
+impl FilterSchedule {
+    pub fn from_env() -> Self {
+        Self {
+            interval: Duration::from_millis(env_or("FILTER_CHECK_INTERVAL", 2_000)),
+            duration: Duration::from_millis(env_or("FILTER_CHECK_DURATION", 60_000)),
+            consecutive_matches: env_or("CONSECUTIVE_FILTER_MATCHES", 3),
+        }
+    }
+
+    // Evaluate once instead of repeatedly
+    pub fn once() -> Self {
+        Self {
+            interval: Duration::ZERO,
+            duration: Duration::ZERO,
+            consecutive_matches: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FilterDecision {
+    Buy(FilterReport),
+    Reject {
+        reason: String,
+        // Last evaluation, if any succeeded
+        report: Option<FilterReport>,
+    },
+}
+
+// Re-runs the pipeline on an interval and only lets a token through after
+// CONSECUTIVE_FILTER_MATCHES passes in a row within FILTER_CHECK_DURATION
+pub struct FilterScheduler {
+    pipeline: FilterPipeline,
+    schedule: FilterSchedule,
+}
+
+impl FilterScheduler {
+    pub fn new(pipeline: FilterPipeline, schedule: FilterSchedule) -> Self {
+        Self { pipeline, schedule }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(FilterPipeline::from_env(), FilterSchedule::from_env())
+    }
+
+    pub fn pipeline(&self) -> &FilterPipeline {
+        &self.pipeline
+    }
+
+    pub async fn decide(&self, rpc_client: &RpcClient, token: &NewToken) -> FilterDecision {
+        let schedule = if self.schedule.interval.is_zero() || self.schedule.duration.is_zero() {
+            FilterSchedule::once()
+        } else {
+            self.schedule
+        };
+        let required = schedule.consecutive_matches.max(1);
+
+        let deadline = Instant::now() + schedule.duration;
+        let mut matches = 0;
+        let mut last_report = None;
+
+        loop {
+            match self.pipeline.run(rpc_client, token).await {
+                Ok(report) if report.passed() => {
+                    matches += 1;
+                    debug!("{} matched filters {}/{}", token.mint, matches, required);
+                    if matches >= required {
+                        return FilterDecision::Buy(report);
+                    }
+                    last_report = Some(report);
+                }
+                Ok(report) => {
+                    matches = 0;
+                    last_report = Some(report);
+                }
+                Err(err) => {
+                    warn!("failed to evaluate filters for {}: {}", token.mint, err);
+                    matches = 0;
+                }
+            }
+
+            if Instant::now() + schedule.interval > deadline {
+                break;
+            }
+            tokio::time::sleep(schedule.interval).await;
+        }
+
+        let reason = match last_report
+            .as_ref()
+            .and_then(|report| report.failures().last())
+        {
+            Some(failure) => format!("{}: {}", failure.filter, failure.reason),
+            None => format!(
+                "no {} consecutive filter matches within {:?}",
+                required, schedule.duration
+            ),
+        };
+        FilterDecision::Reject {
+            reason,
+            report: last_report,
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use super::{Filter, FilterContext, FilterResult};
+use crate::config::env_or;
+
+// REQUIRE_TWITTER / REQUIRE_TELEGRAM / REQUIRE_WEBSITE: the off-chain metadata must link them
+pub struct SocialsFilter {
+    pub twitter: bool,
+    pub telegram: bool,
+    pub website: bool,
+}
+
+impl SocialsFilter {
+    pub fn from_env() -> Self {
+        Self {
+            twitter: env_or("REQUIRE_TWITTER", false),
+            telegram: env_or("REQUIRE_TELEGRAM", false),
+            website: env_or("REQUIRE_WEBSITE", false),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.twitter || self.telegram || self.website
+    }
+}
+
+fn is_set(link: &Option<String>) -> bool {
+    link.as_deref().is_some_and(|link| !link.trim().is_empty())
+}
+
+impl Filter for SocialsFilter {
+    fn name(&self) -> &'static str {
+        "socials"
+    }
+
+    fn needs_offchain(&self) -> bool {
+        true
+    }
+
+    fn check(&self, context: &FilterContext) -> FilterResult {
+        let Some(offchain) = &context.token.offchain else {
+            return FilterResult::fail(self.name(), "off-chain metadata not resolved");
+        };
+
+        let missing: Vec<&str> = [
+            (self.twitter, &offchain.twitter, "twitter"),
+            (self.telegram, &offchain.telegram, "telegram"),
+            (self.website, &offchain.website, "website"),
+        ]
+        .into_iter()
+        .filter(|(required, link, _)| *required && !is_set(link))
+        .map(|(_, _, social)| social)
+        .collect();
+
+        if missing.is_empty() {
+            FilterResult::pass(self.name(), "has the required socials")
+        } else {
+            FilterResult::fail(self.name(), format!("no {}", missing.join(", ")))
+        }
+    }
+}
+
+// CHECK_IF_TWITTER_REUSED: the twitter handle must not be linked by another recent launch
+pub struct TwitterReuseFilter;
+
+impl Filter for TwitterReuseFilter {
+    fn name(&self) -> &'static str {
+        "twitter reuse"
+    }
+
+    fn needs_offchain(&self) -> bool {
+        true
+    }
+
+    fn check(&self, context: &FilterContext) -> FilterResult {
+        match &context.token.annotations.twitter_reused {
+            Some((handle, mint)) => FilterResult::fail(
+                self.name(),
+                format!("twitter @{} already used by {}", handle, mint),
+            ),
+            None => FilterResult::pass(self.name(), "twitter not reused"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::launch::NewToken;
+    use crate::offchain::OffchainMetadata;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn context(offchain: Option<OffchainMetadata>) -> FilterContext {
+        FilterContext {
+            token: NewToken::new(String::new(), Pubkey::new_unique(), Pubkey::new_unique())
+                .with_offchain(offchain),
+            mint: None,
+            metadata: None,
+            bonding_curve: None,
+        }
+    }
+
+    #[test]
+    fn requires_the_enabled_socials() {
+        let filter = SocialsFilter {
+            twitter: true,
+            telegram: true,
+            website: false,
+        };
+        assert!(filter.is_enabled());
+
+        let context = context(Some(OffchainMetadata {
+            twitter: Some("https://x.com/pepe".to_string()),
+            telegram: Some(" ".to_string()),
+            ..OffchainMetadata::default()
+        }));
+        let result = filter.check(&context);
+        assert!(!result.passed);
+        assert_eq!(result.reason, "no telegram");
+
+        let result = SocialsFilter {
+            twitter: true,
+            telegram: false,
+            website: false,
+        }
+        .check(&context);
+        assert!(result.passed);
+    }
+
+    #[test]
+    fn fails_without_offchain_metadata() {
+        let filter = SocialsFilter {
+            twitter: false,
+            telegram: false,
+            website: true,
+        };
+        assert!(!filter.check(&context(None)).passed);
+    }
+
+    #[test]
+    fn rejects_reused_twitter() {
+        let mut context = context(None);
+        assert!(TwitterReuseFilter.check(&context).passed);
+
+        let earlier = Pubkey::new_unique();
+        context.token.annotations.twitter_reused = Some(("pepe".to_string(), earlier));
+        let result = TwitterReuseFilter.check(&context);
+        assert!(!result.passed);
+        assert_eq!(
+            result.reason,
+            format!("twitter @pepe already used by {}", earlier)
+        );
+    }
+}
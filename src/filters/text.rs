@@ -0,0 +1,224 @@
+use super::{Filter, FilterContext, FilterResult};
+use crate::config::env_or;
+use log::warn;
+use regex::Regex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextField {
+    Name,
+    Symbol,
+    Description,
+}
+
+impl TextField {
+    fn text(self, context: &FilterContext) -> Option<&str> {
+        let token = &context.token;
+        let offchain = token.offchain.as_ref();
+        match self {
+            Self::Name => token
+                .name()
+                .or_else(|| offchain.and_then(|offchain| offchain.name.as_deref())),
+            Self::Symbol => token
+                .symbol()
+                .or_else(|| offchain.and_then(|offchain| offchain.symbol.as_deref())),
+            Self::Description => offchain.and_then(|offchain| offchain.description.as_deref()),
+        }
+    }
+}
+
+fn regex_from_env(key: &str) -> Option<Regex> {
+    let pattern = env_or(key, String::new());
+    if pattern.is_empty() {
+        return None;
+    }
+    match Regex::new(&pattern) {
+        Ok(regex) => Some(regex),
+        Err(err) => {
+            warn!("ignoring invalid {} regex: {}", key, err);
+            None
+        }
+    }
+}
+
+// {NAME,SYMBOL,DESCRIPTION}_ALLOW must match and {NAME,SYMBOL,DESCRIPTION}_DENY must not.
+// Patterns are case sensitive unless they start with (?i).
+pub struct TextFilter {
+    pub field: TextField,
+    pub allow: Option<Regex>,
+    pub deny: Option<Regex>,
+}
+
+impl TextFilter {
+    pub fn new(field: TextField, allow: Option<Regex>, deny: Option<Regex>) -> Self {
+        Self { field, allow, deny }
+    }
+
+    // One filter per field with a pattern set
+    pub fn from_env() -> Vec<Self> {
+        [
+            (TextField::Name, "NAME"),
+            (TextField::Symbol, "SYMBOL"),
+            (TextField::Description, "DESCRIPTION"),
+        ]
+        .into_iter()
+        .map(|(field, prefix)| {
+            Self::new(
+                field,
+                regex_from_env(&format!("{}_ALLOW", prefix)),
+                regex_from_env(&format!("{}_DENY", prefix)),
+            )
+        })
+        .filter(|filter| filter.allow.is_some() || filter.deny.is_some())
+        .collect()
+    }
+}
+
+impl Filter for TextFilter {
+    fn name(&self) -> &'static str {
+        match self.field {
+            TextField::Name => "name",
+            TextField::Symbol => "symbol",
+            TextField::Description => "description",
+        }
+    }
+
+    // Names and symbols come with the create transaction or the metadata account
+    fn needs_offchain(&self) -> bool {
+        self.field == TextField::Description
+    }
+
+    fn check(&self, context: &FilterContext) -> FilterResult {
+        let Some(text) = self.field.text(context) else {
+            return match self.allow {
+                Some(_) => FilterResult::fail(self.name(), "not available"),
+                None => FilterResult::pass(self.name(), "not available"),
+            };
+        };
+
+        if let Some(deny) = &self.deny {
+            if let Some(found) = deny.find(text) {
+                return FilterResult::fail(
+                    self.name(),
+                    format!("contains banned \"{}\"", found.as_str()),
+                );
+            }
+        }
+        if let Some(allow) = &self.allow {
+            if !allow.is_match(text) {
+                return FilterResult::fail(
+                    self.name(),
+                    format!("\"{}\" does not match {}", text, allow),
+                );
+            }
+        }
+        FilterResult::pass(self.name(), format!("\"{}\" allowed", text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::FilterPipeline;
+    use crate::launch::{CreateArgs, NewToken};
+    use crate::offchain::OffchainMetadata;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn context(
+        create_args: Option<CreateArgs>,
+        offchain: Option<OffchainMetadata>,
+    ) -> FilterContext {
+        FilterContext {
+            token: NewToken::new(String::new(), Pubkey::new_unique(), Pubkey::new_unique())
+                .with_create_args(create_args)
+                .with_offchain(offchain),
+            mint: None,
+            metadata: None,
+            bonding_curve: None,
+        }
+    }
+
+    fn pepe() -> FilterContext {
+        context(
+            Some(CreateArgs {
+                name: "Pepe Coin".to_string(),
+                symbol: "PEPE".to_string(),
+                uri: String::new(),
+            }),
+            Some(OffchainMetadata {
+                name: Some("Offchain Name".to_string()),
+                description: Some("the frog of all frogs".to_string()),
+                ..OffchainMetadata::default()
+            }),
+        )
+    }
+
+    fn regex(pattern: &str) -> Option<Regex> {
+        Some(Regex::new(pattern).unwrap())
+    }
+
+    #[test]
+    fn deny_and_allow_patterns() {
+        let context = pepe();
+        assert!(
+            TextFilter::new(TextField::Name, regex("(?i)pepe"), None)
+                .check(&context)
+                .passed
+        );
+        assert!(
+            !TextFilter::new(TextField::Name, regex("pepe"), None)
+                .check(&context)
+                .passed
+        );
+
+        let denied = TextFilter::new(TextField::Description, regex("frog"), regex("(?i)FROGS"))
+            .check(&context);
+        assert!(!denied.passed);
+        assert_eq!(denied.reason, "contains banned \"frogs\"");
+        assert!(
+            TextFilter::new(TextField::Symbol, None, regex("^DOG"))
+                .check(&context)
+                .passed
+        );
+    }
+
+    #[test]
+    fn create_args_come_before_offchain_metadata() {
+        let filter = TextFilter::new(TextField::Name, regex("^Pepe Coin$"), None);
+        assert!(filter.check(&pepe()).passed);
+
+        let offchain_only = context(
+            None,
+            Some(OffchainMetadata {
+                name: Some("Pepe Coin".to_string()),
+                ..OffchainMetadata::default()
+            }),
+        );
+        assert!(filter.check(&offchain_only).passed);
+    }
+
+    #[test]
+    fn missing_text_only_fails_an_allow_pattern() {
+        let context = context(None, None);
+        assert!(
+            !TextFilter::new(TextField::Description, regex("."), None)
+                .check(&context)
+                .passed
+        );
+        assert!(
+            TextFilter::new(TextField::Description, None, regex("."))
+                .check(&context)
+                .passed
+        );
+    }
+
+    #[test]
+    fn only_description_patterns_wait_for_offchain_metadata() {
+        let name = || TextFilter::new(TextField::Name, regex("."), None);
+        let description = || TextFilter::new(TextField::Description, regex("."), None);
+        assert!(!FilterPipeline::new().with(name()).needs_offchain());
+        assert!(FilterPipeline::new()
+            .with(name())
+            .with(description())
+            .needs_offchain());
+    }
+}
@@ -0,0 +1,305 @@
+use crate::config::env_or;
+use crate::pump;
+use crate::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+#[allow(deprecated)]
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use std::time::{Duration, Instant};
+
+pub const JITO_TIP_ACCOUNTS: [Pubkey; 8] = [
+    pubkey!("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5"),
+    pubkey!("HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe"),
+    pubkey!("Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY"),
+    pubkey!("ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49"),
+    pubkey!("DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh"),
+    pubkey!("ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt"),
+    pubkey!("DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL"),
+    pubkey!("3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT"),
+];
+
+// The block engine rejects bundles with more transactions than this
+pub const MAX_BUNDLE_SIZE: usize = 5;
+
+const DEFAULT_BLOCK_ENGINE_URL: &str = "https://mainnet.block-engine.jito.wtf";
+
+// Pick one of the tip accounts at random so our bundles don't all contend on the same account
+pub fn random_tip_account() -> Pubkey {
+    *JITO_TIP_ACCOUNTS.choose(&mut rand::thread_rng()).unwrap()
+}
+
+pub fn tip_instruction(payer: &Pubkey, lamports: u64) -> Instruction {
+    system_instruction::transfer(payer, &random_tip_account(), lamports)
+}
+
+// Groups our own transactions into one bundle. The tip is added to the last transaction,
+// so it is only paid when everything before it has landed too.
+pub struct BundleBuilder<'a> {
+    payer: &'a Keypair,
+    recent_blockhash: Hash,
+    transactions: Vec<Vec<Instruction>>,
+}
+
+impl<'a> BundleBuilder<'a> {
+    pub fn new(payer: &'a Keypair, recent_blockhash: Hash) -> Self {
+        Self {
+            payer,
+            recent_blockhash,
+            transactions: Vec::new(),
+        }
+    }
+
+    // Append a transaction made of the given instructions
+    pub fn add_transaction(mut self, instructions: Vec<Instruction>) -> Self {
+        self.transactions.push(instructions);
+        self
+    }
+
+    pub fn build(self, tip_lamports: u64) -> Result<Vec<Transaction>> {
+        if self.transactions.is_empty() {
+            return Err("bundle has no transactions".into());
+        }
+        if self.transactions.len() > MAX_BUNDLE_SIZE {
+            return Err(format!(
+                "bundle has {} transactions, at most {} are allowed",
+                self.transactions.len(),
+                MAX_BUNDLE_SIZE
+            )
+            .into());
+        }
+
+        let payer = self.payer.pubkey();
+        let mut transactions = self.transactions;
+        transactions
+            .last_mut()
+            .unwrap()
+            .push(tip_instruction(&payer, tip_lamports));
+
+        Ok(transactions
+            .iter()
+            .map(|instructions| {
+                Transaction::new_signed_with_payer(
+                    instructions,
+                    Some(&payer),
+                    &[self.payer],
+                    self.recent_blockhash,
+                )
+            })
+            .collect())
+    }
+}
+
+// Single transaction bundle creating our token account, buying and tipping atomically
+#[allow(clippy::too_many_arguments)]
+pub fn buy_bundle(
+    payer: &Keypair,
+    mint: &Pubkey,
+    creator: &Pubkey,
+    token_program: &Pubkey,
+    token_amount: u64,
+    max_sol_cost: u64,
+    tip_lamports: u64,
+    recent_blockhash: Hash,
+) -> Result<Vec<Transaction>> {
+    let user = payer.pubkey();
+    BundleBuilder::new(payer, recent_blockhash)
+        .add_transaction(vec![
+            create_associated_token_account_idempotent(&user, &user, mint, token_program),
+            pump::buy_instruction(
+                &user,
+                mint,
+                creator,
+                token_program,
+                token_amount,
+                max_sol_cost,
+            ),
+        ])
+        .build(tip_lamports)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BundleStatus {
+    pub bundle_id: String,
+    pub transactions: Vec<String>,
+    pub slot: u64,
+    pub confirmation_status: Option<String>,
+    pub err: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum InflightStatus {
+    Invalid,
+    Pending,
+    Failed,
+    Landed,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InflightBundleStatus {
+    pub bundle_id: String,
+    pub status: InflightStatus,
+    pub landed_slot: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleOutcome {
+    Landed { slot: u64 },
+    Failed,
+    Invalid,
+    TimedOut,
+}
+
+// JSON-RPC client for a Jito block engine
+pub struct BlockEngineClient {
+    http: reqwest::Client,
+    url: String,
+}
+
+impl BlockEngineClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(&env_or(
+            "JITO_BLOCK_ENGINE_URL",
+            DEFAULT_BLOCK_ENGINE_URL.to_string(),
+        ))
+    }
+
+    async fn call(&self, path: &str, method: &str, params: Value) -> Result<Value> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let response: Value = self
+            .http
+            .post(format!("{}{}", self.url, path))
+            .json(&request)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            return Err(format!("{} failed: {}", method, error).into());
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| format!("{} returned no result", method).into())
+    }
+
+    // Submit a bundle, returning the bundle id
+    pub async fn send_bundle(&self, transactions: &[Transaction]) -> Result<String> {
+        let encoded = transactions
+            .iter()
+            .map(|transaction| Ok(STANDARD.encode(bincode::serialize(transaction)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let result = self
+            .call(
+                "/api/v1/bundles",
+                "sendBundle",
+                json!([encoded, { "encoding": "base64" }]),
+            )
+            .await?;
+        result
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("unexpected sendBundle result: {}", result).into())
+    }
+
+    // Statuses of landed bundles, `None` for ids the engine doesn't know about
+    pub async fn get_bundle_statuses(
+        &self,
+        bundle_ids: &[String],
+    ) -> Result<Vec<Option<BundleStatus>>> {
+        let result = self
+            .call(
+                "/api/v1/getBundleStatuses",
+                "getBundleStatuses",
+                json!([bundle_ids]),
+            )
+            .await?;
+        Ok(serde_json::from_value(result["value"].clone())?)
+    }
+
+    pub async fn get_inflight_bundle_statuses(
+        &self,
+        bundle_ids: &[String],
+    ) -> Result<Vec<InflightBundleStatus>> {
+        let result = self
+            .call(
+                "/api/v1/getInflightBundleStatuses",
+                "getInflightBundleStatuses",
+                json!([bundle_ids]),
+            )
+            .await?;
+        Ok(serde_json::from_value(result["value"].clone())?)
+    }
+
+    pub async fn get_tip_accounts(&self) -> Result<Vec<Pubkey>> {
+        let result = self
+            .call("/api/v1/getTipAccounts", "getTipAccounts", json!([]))
+            .await?;
+        let accounts: Vec<String> = serde_json::from_value(result)?;
+        accounts
+            .iter()
+            .map(|account| Ok(account.parse::<Pubkey>()?))
+            .collect()
+    }
+
+    // Poll the in-flight status until the bundle lands, fails or the timeout passes.
+    // A bundle reads as Invalid until the engine has seen it, so that is only final at the timeout.
+    pub async fn wait_for_bundle(
+        &self,
+        bundle_id: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<BundleOutcome> {
+        let started = Instant::now();
+        let mut last_status = None;
+
+        loop {
+            let statuses = self
+                .get_inflight_bundle_statuses(&[bundle_id.to_string()])
+                .await?;
+            if let Some(status) = statuses.into_iter().next() {
+                match status.status {
+                    InflightStatus::Landed => {
+                        return Ok(BundleOutcome::Landed {
+                            slot: status.landed_slot.unwrap_or_default(),
+                        })
+                    }
+                    InflightStatus::Failed => return Ok(BundleOutcome::Failed),
+                    other => last_status = Some(other),
+                }
+            }
+
+            if started.elapsed() >= timeout {
+                return Ok(match last_status {
+                    Some(InflightStatus::Invalid) => BundleOutcome::Invalid,
+                    _ => BundleOutcome::TimedOut,
+                });
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::decode::Reader;
+use crate::events::CreateEvent;
+use crate::metadata::Metadata;
+use crate::offchain::OffchainMetadata;
+use crate::pump;
+use crate::recent::Annotations;
+use solana_sdk::pubkey::Pubkey;
+
+const CREATE_DISCRIMINATOR: [u8; 8] = [24, 30, 200, 40, 5, 28, 7, 119];
+
+// Positions of the mint and the creator in the create instruction accounts
+pub const CREATE_MINT_ACCOUNT: usize = 0;
+pub const CREATE_USER_ACCOUNT: usize = 7;
+
+// Name, symbol and uri the creator passed to the create instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateArgs {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+impl CreateArgs {
+    // From the create instruction data, None for any other instruction
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.get(..8)? != CREATE_DISCRIMINATOR {
+            return None;
+        }
+        let mut reader = Reader::new(&data[8..]);
+
+        Some(Self {
+            name: reader.string()?,
+            symbol: reader.string()?,
+            uri: reader.string()?,
+        })
+    }
+}
+
+// A token just created on pump.fun
+#[derive(Debug, Clone)]
+pub struct NewToken {
+    pub signature: String,
+    pub mint: Pubkey,
+    pub creator: Pubkey,
+    pub bonding_curve: Pubkey,
+    // Decoded from the create transaction itself, available without waiting on any account
+    pub create_args: Option<CreateArgs>,
+    // Metaplex metadata, read when the token was seen only if the create arguments were not decoded
+    pub metadata: Option<Metadata>,
+    // The JSON behind the uri, once resolved
+    pub offchain: Option<OffchainMetadata>,
+    // Set by the recent launches index
+    pub annotations: Annotations,
+}
+
+impl NewToken {
+    pub fn new(signature: String, mint: Pubkey, creator: Pubkey) -> Self {
+        Self {
+            signature,
+            mint,
+            creator,
+            bonding_curve: pump::bonding_curve_address(&mint),
+            create_args: None,
+            metadata: None,
+            offchain: None,
+            annotations: Annotations::default(),
+        }
+    }
+
+    pub fn from_create_event(signature: String, event: &CreateEvent) -> Self {
+        Self {
+            signature,
+            mint: event.mint,
+            creator: event.user,
+            bonding_curve: event.bonding_curve,
+            create_args: Some(CreateArgs {
+                name: event.name.clone(),
+                symbol: event.symbol.clone(),
+                uri: event.uri.clone(),
+            }),
+            metadata: None,
+            offchain: None,
+            annotations: Annotations::default(),
+        }
+    }
+
+    pub fn with_create_args(mut self, create_args: Option<CreateArgs>) -> Self {
+        self.create_args = create_args;
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: Option<Metadata>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn with_offchain(mut self, offchain: Option<OffchainMetadata>) -> Self {
+        self.offchain = offchain;
+        self
+    }
+
+    pub fn with_annotations(mut self, annotations: Annotations) -> Self {
+        self.annotations = annotations;
+        self
+    }
+
+    // From the create arguments, falling back to the metadata account
+    pub fn name(&self) -> Option<&str> {
+        match (&self.create_args, &self.metadata) {
+            (Some(args), _) => Some(&args.name),
+            (None, Some(metadata)) => Some(&metadata.name),
+            (None, None) => None,
+        }
+    }
+
+    pub fn symbol(&self) -> Option<&str> {
+        match (&self.create_args, &self.metadata) {
+            (Some(args), _) => Some(&args.symbol),
+            (None, Some(metadata)) => Some(&metadata.symbol),
+            (None, None) => None,
+        }
+    }
+
+    pub fn uri(&self) -> Option<&str> {
+        match (&self.create_args, &self.metadata) {
+            (Some(args), _) => Some(&args.uri),
+            (None, Some(metadata)) => Some(&metadata.uri),
+            (None, None) => None,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+pub mod blacklist;
+pub mod blockhash;
+pub mod config;
+pub mod copy_trade;
+mod decode;
+pub mod events;
+pub mod executor;
+pub mod fees;
+pub mod filters;
+pub mod jito;
+pub mod launch;
+pub mod logger;
+pub mod metadata;
+pub mod mint;
+pub mod offchain;
+pub mod position;
+pub mod price_feed;
+pub mod pump;
+pub mod recent;
+pub mod simulation;
+pub mod snipe_list;
+pub mod sniper;
+pub mod tracker;
+pub mod wallets;
+pub mod watchlist;
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+pub type Result<T> = std::result::Result<T, Error>;
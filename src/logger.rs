@@ -0,0 +1,27 @@
+use crate::config::env_or;
+use chrono::Local;
+use fern::colors::{Color, ColoredLevelConfig};
+use log::LevelFilter;
+
+// Colored console logging at LOG_LEVEL (info by default)
+pub fn console_color_initialize() {
+    let colors = ColoredLevelConfig::new()
+        .error(Color::Red)
+        .warn(Color::Yellow)
+        .info(Color::Green)
+        .debug(Color::Magenta);
+
+    fern::Dispatch::new()
+        .format(move |out, message, record| {
+            out.finish(format_args!(
+                "{} [{}] {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                colors.color(record.level()),
+                message
+            ))
+        })
+        .level(env_or("LOG_LEVEL", LevelFilter::Info))
+        .chain(std::io::stdout())
+        .apply()
+        .unwrap();
+}
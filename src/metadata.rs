@@ -0,0 +1,87 @@
+use crate::decode::Reader;
+use crate::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+pub const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+// Account key of a v1 metadata account
+const METADATA_V1_KEY: u8 = 4;
+
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Creator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+// Metaplex token metadata, up to the fields we use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Vec<Creator>,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+}
+
+impl Metadata {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader::new(data);
+        if reader.u8()? != METADATA_V1_KEY {
+            return None;
+        }
+
+        let update_authority = reader.pubkey()?;
+        let mint = reader.pubkey()?;
+        let name = reader.string()?;
+        let symbol = reader.string()?;
+        let uri = reader.string()?;
+        let seller_fee_basis_points = reader.u16()?;
+
+        let mut creators = Vec::new();
+        if reader.bool()? {
+            for _ in 0..reader.u32()? {
+                creators.push(Creator {
+                    address: reader.pubkey()?,
+                    verified: reader.bool()?,
+                    share: reader.u8()?,
+                });
+            }
+        }
+
+        Some(Self {
+            update_authority,
+            mint,
+            name,
+            symbol,
+            uri,
+            seller_fee_basis_points,
+            creators,
+            primary_sale_happened: reader.bool()?,
+            is_mutable: reader.bool()?,
+        })
+    }
+}
+
+// Metadata of the mint, None while the account doesn't exist
+pub async fn fetch_metadata(rpc_client: &RpcClient, mint: &Pubkey) -> Result<Option<Metadata>> {
+    let account = rpc_client
+        .get_account_with_commitment(&metadata_address(mint), rpc_client.commitment())
+        .await?
+        .value;
+    Ok(account.and_then(|account| Metadata::decode(&account.data)))
+}
@@ -0,0 +1,358 @@
+use crate::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::metadata_pointer::MetadataPointer;
+use spl_token_2022::extension::mint_close_authority::MintCloseAuthority;
+use spl_token_2022::extension::permanent_delegate::PermanentDelegate;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::transfer_hook::TransferHook;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenProgram {
+    Token,
+    Token2022,
+}
+
+impl TokenProgram {
+    pub fn from_owner(owner: &Pubkey) -> Option<Self> {
+        if *owner == spl_token::id() {
+            Some(Self::Token)
+        } else if *owner == spl_token_2022::id() {
+            Some(Self::Token2022)
+        } else {
+            None
+        }
+    }
+
+    pub fn id(&self) -> Pubkey {
+        match self {
+            Self::Token => spl_token::id(),
+            Self::Token2022 => spl_token_2022::id(),
+        }
+    }
+}
+
+// The token program owning `mint`
+pub async fn fetch_token_program(rpc_client: &RpcClient, mint: &Pubkey) -> Result<TokenProgram> {
+    let account = rpc_client.get_account(mint).await?;
+    TokenProgram::from_owner(&account.owner)
+        .ok_or_else(|| format!("{} is not owned by a token program", mint).into())
+}
+
+impl fmt::Display for TokenProgram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Token => write!(f, "Token"),
+            Self::Token2022 => write!(f, "Token-2022"),
+        }
+    }
+}
+
+// Token-2022 mint extensions we look at, anything else is kept by type
+#[derive(Debug, Clone, PartialEq)]
+pub enum MintExtension {
+    TransferFee {
+        basis_points: u16,
+        maximum_fee: u64,
+        authority: Option<Pubkey>,
+    },
+    TransferHook {
+        authority: Option<Pubkey>,
+        program_id: Option<Pubkey>,
+    },
+    PermanentDelegate {
+        delegate: Option<Pubkey>,
+    },
+    MetadataPointer {
+        authority: Option<Pubkey>,
+        metadata_address: Option<Pubkey>,
+    },
+    MintCloseAuthority {
+        authority: Option<Pubkey>,
+    },
+    Other(ExtensionType),
+}
+
+impl MintExtension {
+    // Extensions that let someone take a cut of, block or take back our tokens
+    pub fn is_risky(&self) -> bool {
+        match self {
+            Self::TransferFee {
+                basis_points,
+                authority,
+                ..
+            } => *basis_points > 0 || authority.is_some(),
+            Self::TransferHook { program_id, .. } => program_id.is_some(),
+            Self::PermanentDelegate { delegate } => delegate.is_some(),
+            Self::MintCloseAuthority { authority } => authority.is_some(),
+            Self::MetadataPointer { .. } | Self::Other(_) => false,
+        }
+    }
+}
+
+fn or_none(pubkey: &Option<Pubkey>) -> String {
+    pubkey.map_or("none".to_string(), |pubkey| pubkey.to_string())
+}
+
+impl fmt::Display for MintExtension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TransferFee {
+                basis_points,
+                maximum_fee,
+                authority,
+            } => write!(
+                f,
+                "transfer fee {} bps (max {}), authority {}",
+                basis_points,
+                maximum_fee,
+                or_none(authority)
+            ),
+            Self::TransferHook {
+                authority,
+                program_id,
+            } => write!(
+                f,
+                "transfer hook program {}, authority {}",
+                or_none(program_id),
+                or_none(authority)
+            ),
+            Self::PermanentDelegate { delegate } => {
+                write!(f, "permanent delegate {}", or_none(delegate))
+            }
+            Self::MetadataPointer {
+                authority,
+                metadata_address,
+            } => write!(
+                f,
+                "metadata pointer {}, authority {}",
+                or_none(metadata_address),
+                or_none(authority)
+            ),
+            Self::MintCloseAuthority { authority } => {
+                write!(f, "mint close authority {}", or_none(authority))
+            }
+            Self::Other(extension) => write!(f, "{:?}", extension),
+        }
+    }
+}
+
+// A mint of either token program
+#[derive(Debug, Clone, PartialEq)]
+pub struct MintInfo {
+    pub program: TokenProgram,
+    pub mint_authority: Option<Pubkey>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<Pubkey>,
+    pub extensions: Vec<MintExtension>,
+}
+
+impl MintInfo {
+    // None for accounts that aren't a mint of either token program
+    pub fn unpack(owner: &Pubkey, data: &[u8]) -> Option<Self> {
+        match TokenProgram::from_owner(owner)? {
+            TokenProgram::Token => {
+                let mint = spl_token::state::Mint::unpack(data).ok()?;
+                Some(Self {
+                    program: TokenProgram::Token,
+                    mint_authority: mint.mint_authority.into(),
+                    supply: mint.supply,
+                    decimals: mint.decimals,
+                    is_initialized: mint.is_initialized,
+                    freeze_authority: mint.freeze_authority.into(),
+                    extensions: Vec::new(),
+                })
+            }
+            TokenProgram::Token2022 => {
+                let state =
+                    StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data).ok()?;
+                Some(Self {
+                    program: TokenProgram::Token2022,
+                    mint_authority: state.base.mint_authority.into(),
+                    supply: state.base.supply,
+                    decimals: state.base.decimals,
+                    is_initialized: state.base.is_initialized,
+                    freeze_authority: state.base.freeze_authority.into(),
+                    extensions: extensions(&state),
+                })
+            }
+        }
+    }
+
+    pub fn risky_extensions(&self) -> impl Iterator<Item = &MintExtension> {
+        self.extensions
+            .iter()
+            .filter(|extension| extension.is_risky())
+    }
+}
+
+fn extensions(state: &StateWithExtensions<spl_token_2022::state::Mint>) -> Vec<MintExtension> {
+    let Ok(types) = state.get_extension_types() else {
+        return Vec::new();
+    };
+
+    types
+        .into_iter()
+        .filter_map(|extension_type| {
+            Some(match extension_type {
+                ExtensionType::TransferFeeConfig => {
+                    let config = state.get_extension::<TransferFeeConfig>().ok()?;
+                    MintExtension::TransferFee {
+                        basis_points: u16::from(
+                            config.newer_transfer_fee.transfer_fee_basis_points,
+                        ),
+                        maximum_fee: u64::from(config.newer_transfer_fee.maximum_fee),
+                        authority: config.transfer_fee_config_authority.into(),
+                    }
+                }
+                ExtensionType::TransferHook => {
+                    let hook = state.get_extension::<TransferHook>().ok()?;
+                    MintExtension::TransferHook {
+                        authority: hook.authority.into(),
+                        program_id: hook.program_id.into(),
+                    }
+                }
+                ExtensionType::PermanentDelegate => {
+                    let delegate = state.get_extension::<PermanentDelegate>().ok()?;
+                    MintExtension::PermanentDelegate {
+                        delegate: delegate.delegate.into(),
+                    }
+                }
+                ExtensionType::MetadataPointer => {
+                    let pointer = state.get_extension::<MetadataPointer>().ok()?;
+                    MintExtension::MetadataPointer {
+                        authority: pointer.authority.into(),
+                        metadata_address: pointer.metadata_address.into(),
+                    }
+                }
+                ExtensionType::MintCloseAuthority => {
+                    let close = state.get_extension::<MintCloseAuthority>().ok()?;
+                    MintExtension::MintCloseAuthority {
+                        authority: close.close_authority.into(),
+                    }
+                }
+                other => MintExtension::Other(other),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::program_option::COption;
+    use spl_token_2022::extension::{BaseStateWithExtensionsMut, StateWithExtensionsMut};
+
+    fn token_2022_mint(
+        extension_types: &[ExtensionType],
+        init: impl FnOnce(&mut StateWithExtensionsMut<spl_token_2022::state::Mint>),
+    ) -> Vec<u8> {
+        let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(
+            extension_types,
+        )
+        .unwrap();
+        let mut data = vec![0; len];
+        let mut state =
+            StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data)
+                .unwrap();
+        init(&mut state);
+        state.base.supply = 1_000_000;
+        state.base.decimals = 6;
+        state.base.is_initialized = true;
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    fn unpacks_a_token_mint() {
+        let authority = Pubkey::new_unique();
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            mint_authority: COption::Some(authority),
+            supply: 42,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+
+        let mint = MintInfo::unpack(&spl_token::id(), &data).unwrap();
+        assert_eq!(mint.program, TokenProgram::Token);
+        assert_eq!(mint.mint_authority, Some(authority));
+        assert_eq!(mint.freeze_authority, None);
+        assert_eq!(mint.supply, 42);
+        assert!(mint.extensions.is_empty());
+    }
+
+    #[test]
+    fn rejects_accounts_of_other_programs() {
+        let data = token_2022_mint(&[], |_| {});
+        assert_eq!(MintInfo::unpack(&Pubkey::new_unique(), &data), None);
+        assert_eq!(MintInfo::unpack(&spl_token::id(), &[0; 10]), None);
+    }
+
+    #[test]
+    fn transfer_fee_is_risky_only_when_charged_or_changeable() {
+        let authority = Pubkey::new_unique();
+        let data = token_2022_mint(&[ExtensionType::TransferFeeConfig], |state| {
+            let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+            config.newer_transfer_fee.transfer_fee_basis_points = 250.into();
+            config.newer_transfer_fee.maximum_fee = 5_000.into();
+            config.transfer_fee_config_authority = Some(authority).try_into().unwrap();
+        });
+        let mint = MintInfo::unpack(&spl_token_2022::id(), &data).unwrap();
+        assert_eq!(mint.program, TokenProgram::Token2022);
+        assert_eq!(
+            mint.extensions,
+            vec![MintExtension::TransferFee {
+                basis_points: 250,
+                maximum_fee: 5_000,
+                authority: Some(authority),
+            }]
+        );
+        assert_eq!(mint.risky_extensions().count(), 1);
+
+        let data = token_2022_mint(&[ExtensionType::TransferFeeConfig], |state| {
+            state.init_extension::<TransferFeeConfig>(true).unwrap();
+        });
+        let mint = MintInfo::unpack(&spl_token_2022::id(), &data).unwrap();
+        assert_eq!(mint.risky_extensions().count(), 0);
+    }
+
+    #[test]
+    fn flags_permanent_delegates_but_not_metadata_pointers() {
+        let delegate = Pubkey::new_unique();
+        let data = token_2022_mint(
+            &[
+                ExtensionType::PermanentDelegate,
+                ExtensionType::MetadataPointer,
+            ],
+            |state| {
+                state
+                    .init_extension::<PermanentDelegate>(true)
+                    .unwrap()
+                    .delegate = Some(delegate).try_into().unwrap();
+                state.init_extension::<MetadataPointer>(true).unwrap();
+            },
+        );
+        let mint = MintInfo::unpack(&spl_token_2022::id(), &data).unwrap();
+
+        let risky: Vec<_> = mint.risky_extensions().collect();
+        assert_eq!(
+            risky,
+            vec![&MintExtension::PermanentDelegate {
+                delegate: Some(delegate)
+            }]
+        );
+        assert!(mint.extensions.contains(&MintExtension::MetadataPointer {
+            authority: None,
+            metadata_address: None,
+        }));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::config::env_or;
+use crate::Result;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use log::{debug, warn};
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const DEFAULT_GATEWAYS: &str = "https://cf-ipfs.com/ipfs/,https://ipfs.io/ipfs/";
+
+// The JSON a token `uri` points to
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OffchainMetadata {
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub twitter: Option<String>,
+    pub telegram: Option<String>,
+    pub website: Option<String>,
+}
+
+impl OffchainMetadata {
+    pub fn has_socials(&self) -> bool {
+        [&self.twitter, &self.telegram, &self.website]
+            .into_iter()
+            .any(|link| link.as_deref().is_some_and(|link| !link.trim().is_empty()))
+    }
+}
+
+// The content path of an IPFS uri, either `ipfs://<cid>` or a gateway url `.../ipfs/<cid>`
+pub(crate) fn ipfs_path(uri: &str) -> Option<&str> {
+    if let Some(path) = uri.strip_prefix("ipfs://") {
+        return Some(path.trim_start_matches("ipfs/"));
+    }
+    let (_, path) = uri.split_once("/ipfs/")?;
+    (!path.is_empty()).then_some(path)
+}
+
+// Fetches metadata JSON through our own IPFS gateways, bounded in time and size,
+// and keeps every resolved uri on disk
+pub struct MetadataResolver {
+    http: reqwest::Client,
+    gateways: Vec<String>,
+    max_size: usize,
+    cache_dir: Option<PathBuf>,
+}
+
+impl MetadataResolver {
+    pub fn new(gateways: Vec<String>, timeout: Duration, max_size: usize) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .expect("failed to build http client"),
+            gateways: gateways
+                .iter()
+                .map(|gateway| format!("{}/", gateway.trim().trim_end_matches('/')))
+                .collect(),
+            max_size,
+            cache_dir: None,
+        }
+    }
+
+    pub fn with_cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(cache_dir.into());
+        self
+    }
+
+    // METADATA_GATEWAYS comma separated, METADATA_TIMEOUT ms, METADATA_MAX_SIZE bytes,
+    // METADATA_CACHE_DIR, empty to disable the cache
+    pub fn from_env() -> Self {
+        let gateways = env_or("METADATA_GATEWAYS", DEFAULT_GATEWAYS.to_string())
+            .split(',')
+            .filter(|gateway| !gateway.trim().is_empty())
+            .map(str::to_string)
+            .collect();
+        let resolver = Self::new(
+            gateways,
+            Duration::from_millis(env_or("METADATA_TIMEOUT", 3_000)),
+            env_or("METADATA_MAX_SIZE", 64 * 1024),
+        );
+
+        let cache_dir = env_or("METADATA_CACHE_DIR", "metadata-cache".to_string());
+        if cache_dir.is_empty() {
+            resolver
+        } else {
+            resolver.with_cache_dir(cache_dir)
+        }
+    }
+
+    // IPFS content through each gateway before the uri itself, anything else as is
+    pub fn candidate_urls(&self, uri: &str) -> Vec<String> {
+        let mut urls = Vec::new();
+        if let Some(path) = ipfs_path(uri) {
+            urls.extend(
+                self.gateways
+                    .iter()
+                    .map(|gateway| format!("{}{}", gateway, path)),
+            );
+        }
+        if uri.starts_with("http://") || uri.starts_with("https://") {
+            urls.push(uri.to_string());
+        }
+        urls.dedup();
+        urls
+    }
+
+    pub async fn resolve(&self, uri: &str) -> Result<OffchainMetadata> {
+        if let Some(metadata) = self.cached(uri) {
+            return Ok(metadata);
+        }
+
+        // Every candidate at once, the first document wins and the other requests are dropped
+        let mut fetches: FuturesUnordered<_> = self
+            .candidate_urls(uri)
+            .into_iter()
+            .map(|url| async move {
+                let result = self.fetch(&url).await;
+                (url, result)
+            })
+            .collect();
+
+        let mut last_error = None;
+        while let Some((url, result)) = fetches.next().await {
+            match result {
+                Ok(metadata) => {
+                    self.store(uri, &metadata);
+                    return Ok(metadata);
+                }
+                Err(err) => {
+                    debug!("failed to fetch metadata from {}: {}", url, err);
+                    last_error = Some(err);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| format!("unsupported metadata uri: {}", uri).into()))
+    }
+
+    async fn fetch(&self, url: &str) -> Result<OffchainMetadata> {
+        let mut response = self.http.get(url).send().await?.error_for_status()?;
+        if response
+            .content_length()
+            .is_some_and(|length| length > self.max_size as u64)
+        {
+            return Err(format!("metadata larger than {} bytes", self.max_size).into());
+        }
+
+        // The length header is optional, so the body is capped while reading too
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > self.max_size {
+                return Err(format!("metadata larger than {} bytes", self.max_size).into());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    fn cache_path(&self, uri: &str) -> Option<PathBuf> {
+        let hash: String = digest(&SHA256, uri.as_bytes())
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Some(self.cache_dir.as_ref()?.join(format!("{}.json", hash)))
+    }
+
+    fn cached(&self, uri: &str) -> Option<OffchainMetadata> {
+        let contents = std::fs::read(self.cache_path(uri)?).ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+
+    fn store(&self, uri: &str, metadata: &OffchainMetadata) {
+        let Some(path) = self.cache_path(uri) else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, serde_json::to_vec(metadata)?));
+        if let Err(err) = result {
+            warn!("failed to cache metadata of {}: {}", uri, err);
+        }
+    }
+}
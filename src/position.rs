@@ -0,0 +1,654 @@
+use crate::config::{env_or, lamports_to_sol};
+use crate::executor::{ExecutionResult, Executor};
+use crate::price_feed::{PriceFeed, PriceUpdate};
+use crate::pump::{self, BondingCurve};
+use crate::sniper::Purchase;
+use crate::tracker;
+use crate::Result;
+use log::{info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+// Partial exit: once the position is up `gain_percent`, sell `sell_percent` of the tokens bought
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LadderStep {
+    pub gain_percent: f64,
+    pub sell_percent: f64,
+}
+
+// Parse a ladder like "50:30,100:30", skipping malformed steps
+pub fn parse_ladder(spec: &str) -> Vec<LadderStep> {
+    let mut ladder: Vec<LadderStep> = spec
+        .split(',')
+        .filter_map(|step| {
+            let (gain, sell) = step.split_once(':')?;
+            Some(LadderStep {
+                gain_percent: gain.trim().parse().ok()?,
+                sell_percent: sell.trim().parse().ok()?,
+            })
+        })
+        .filter(|step| step.sell_percent > 0.0)
+        .collect();
+    ladder.sort_by(|a, b| a.gain_percent.total_cmp(&b.gain_percent));
+    ladder
+}
+
+// When to get out of a position. Percentages are relative to the entry price, 0 disables a rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SellStrategy {
+    pub take_profit: f64,
+    pub stop_loss: f64,
+    // Percent below the highest price seen since entry
+    pub trailing_stop: f64,
+    pub ladder: Vec<LadderStep>,
+}
+
+// `{PROFILE}_{NAME}` when set, otherwise the plain `NAME`
+fn profile_env<T: FromStr>(profile: &str, name: &str, default: T) -> T {
+    let fallback = env_or(name, default);
+    if profile.is_empty() {
+        return fallback;
+    }
+    env_or(&format!("{}_{}", profile.to_uppercase(), name), fallback)
+}
+
+impl SellStrategy {
+    // Strategy of the STRATEGY_PROFILE profile, or the plain settings without one
+    pub fn from_env() -> Self {
+        Self::from_profile(&env_or("STRATEGY_PROFILE", String::new()))
+    }
+
+    pub fn from_profile(profile: &str) -> Self {
+        Self {
+            take_profit: profile_env(profile, "TAKE_PROFIT", 40.0),
+            stop_loss: profile_env(profile, "STOP_LOSS", 20.0),
+            trailing_stop: profile_env(profile, "TRAILING_STOP", 0.0),
+            ladder: parse_ladder(&profile_env(profile, "TAKE_PROFIT_LADDER", String::new())),
+        }
+    }
+
+    // Next exit for the position at `price` and how many tokens it sells. Ladder steps come before
+    // take profit, which waits for the last step and then leaves the rest to the trailing stop if set.
+    pub fn next_exit(
+        &self,
+        position: &Position,
+        price: f64,
+        expired: bool,
+    ) -> Option<(ExitReason, u64)> {
+        let pnl_percent = position.pnl_percent(price);
+        let remaining = position.remaining;
+
+        if self.stop_loss > 0.0 && pnl_percent <= -self.stop_loss {
+            return Some((ExitReason::StopLoss, remaining));
+        }
+        if self.trailing_stop > 0.0
+            && price <= position.peak_price * (1.0 - self.trailing_stop / 100.0)
+        {
+            return Some((ExitReason::TrailingStop, remaining));
+        }
+        if let Some(step) = self.ladder.get(position.ladder_filled) {
+            if pnl_percent >= step.gain_percent {
+                let amount = (position.token_amount as f64 * step.sell_percent / 100.0) as u64;
+                return Some((
+                    ExitReason::Ladder(position.ladder_filled),
+                    amount.clamp(1, remaining),
+                ));
+            }
+        }
+        let ladder_done = position.ladder_filled >= self.ladder.len();
+        let rest_trails = !self.ladder.is_empty() && self.trailing_stop > 0.0;
+        if self.take_profit > 0.0 && ladder_done && !rest_trails && pnl_percent >= self.take_profit
+        {
+            return Some((ExitReason::TakeProfit, remaining));
+        }
+        if expired {
+            return Some((ExitReason::Expired, remaining));
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SellConfig {
+    pub auto_sell: bool,
+    pub auto_sell_delay: Duration,
+    pub price_check_interval: Duration,
+    pub price_check_duration: Duration,
+    pub strategy: SellStrategy,
+    pub sell_slippage: u64,
+    pub max_sell_retries: u32,
+}
+
+impl SellConfig {
+    pub fn from_env() -> Self {
+        Self {
+            auto_sell: env_or("AUTO_SELL", true),
+            auto_sell_delay: Duration::from_millis(env_or("AUTO_SELL_DELAY", 0)),
+            price_check_interval: Duration::from_millis(env_or("PRICE_CHECK_INTERVAL", 2_000)),
+            price_check_duration: Duration::from_millis(env_or("PRICE_CHECK_DURATION", 600_000)),
+            strategy: SellStrategy::from_env(),
+            sell_slippage: env_or("SELL_SLIPPAGE", 20),
+            max_sell_retries: env_or("MAX_SELL_RETRIES", 10),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Position {
+    pub mint: Pubkey,
+    // Tokens bought, ladder steps sell a share of this
+    pub token_amount: u64,
+    pub remaining: u64,
+    // Lamports spent opening the position and received from exits so far, for the realised PnL
+    pub cost: u64,
+    pub proceeds: u64,
+    // Lamports paid into the bonding curve, what the sell strategy measures gains against.
+    // `cost` also holds the fees, tip and token account rent, a small buy would start deep in the red.
+    pub entry_cost: u64,
+    // Highest lamports per raw token seen since entry
+    pub peak_price: f64,
+    pub ladder_filled: usize,
+    pub exits: Vec<Exit>,
+    pub opened_at: Instant,
+}
+
+impl From<&Purchase> for Position {
+    fn from(purchase: &Purchase) -> Self {
+        let mut position = Self {
+            mint: purchase.mint,
+            token_amount: purchase.token_amount,
+            remaining: purchase.token_amount,
+            cost: purchase.sol_amount,
+            proceeds: 0,
+            entry_cost: purchase.curve_sol_amount,
+            peak_price: 0.0,
+            ladder_filled: 0,
+            exits: Vec::new(),
+            opened_at: Instant::now(),
+        };
+        position.peak_price = position.entry_price();
+        position
+    }
+}
+
+impl Position {
+    // Lamports paid into the curve per raw token
+    pub fn entry_price(&self) -> f64 {
+        if self.token_amount == 0 {
+            return 0.0;
+        }
+        self.entry_cost as f64 / self.token_amount as f64
+    }
+
+    // Percent change of `price` (lamports per raw token) over the entry price
+    pub fn pnl_percent(&self, price: f64) -> f64 {
+        let entry_price = self.entry_price();
+        if entry_price == 0.0 {
+            return 0.0;
+        }
+        (price - entry_price) / entry_price * 100.0
+    }
+
+    fn record(&mut self, exit: Exit) {
+        if let ExitReason::Ladder(step) = exit.reason {
+            self.ladder_filled = step + 1;
+        }
+        self.remaining -= exit.token_amount.min(self.remaining);
+        self.proceeds += exit.proceeds;
+        self.exits.push(exit);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    TakeProfit,
+    StopLoss,
+    TrailingStop,
+    // Partial exit at the given ladder step
+    Ladder(usize),
+    // PRICE_CHECK_DURATION passed without another rule firing
+    Expired,
+}
+
+#[derive(Debug, Clone)]
+pub struct Exit {
+    pub reason: ExitReason,
+    pub token_amount: u64,
+    pub proceeds: u64,
+    pub execution: ExecutionResult,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClosedPosition {
+    pub mint: Pubkey,
+    pub cost: u64,
+    pub proceeds: u64,
+    pub pnl_lamports: i64,
+    pub pnl_percent: f64,
+    pub exits: Vec<Exit>,
+}
+
+// Watches open positions and sells them following the sell strategy, or when the check duration runs out.
+// Prices come from what the remaining tokens would sell for on the bonding curve right now,
+// pushed by the price feed when there is one and polled every PRICE_CHECK_INTERVAL otherwise.
+pub struct PositionManager {
+    config: SellConfig,
+    payer: Arc<Keypair>,
+    rpc_client: Arc<RpcClient>,
+    executor: Arc<Executor>,
+    price_feed: Option<Arc<PriceFeed>>,
+}
+
+impl PositionManager {
+    pub fn new(
+        config: SellConfig,
+        payer: Arc<Keypair>,
+        rpc_client: Arc<RpcClient>,
+        executor: Arc<Executor>,
+    ) -> Self {
+        Self {
+            config,
+            payer,
+            rpc_client,
+            executor,
+            price_feed: None,
+        }
+    }
+
+    pub fn with_price_feed(mut self, price_feed: Arc<PriceFeed>) -> Self {
+        self.price_feed = Some(price_feed);
+        self
+    }
+
+    // Track the position until it is fully sold. Returns None when auto sell is off, the curve completes
+    // or the sells never land, the tokens left are then still held.
+    pub async fn manage(&self, position: Position) -> Option<ClosedPosition> {
+        let mint = position.mint;
+        let updates = self.price_feed.as_ref().map(|feed| {
+            feed.watch(mint);
+            feed.subscribe()
+        });
+
+        let closed = self.track(position, updates).await;
+        if let Some(feed) = &self.price_feed {
+            feed.unwatch(&mint);
+        }
+        closed
+    }
+
+    async fn track(
+        &self,
+        mut position: Position,
+        mut updates: Option<broadcast::Receiver<PriceUpdate>>,
+    ) -> Option<ClosedPosition> {
+        if !self.config.auto_sell {
+            info!("auto sell is disabled, holding {}", position.mint);
+            return None;
+        }
+        if !self.config.auto_sell_delay.is_zero() {
+            tokio::time::sleep(self.config.auto_sell_delay).await;
+        }
+
+        let deadline = Instant::now() + self.config.price_check_duration;
+        loop {
+            let expired = Instant::now() >= deadline;
+
+            match self.current_curve(&position.mint).await {
+                Ok(curve) if curve.complete => {
+                    warn!(
+                        "bonding curve of {} completed, it can't be sold here",
+                        position.mint
+                    );
+                    return None;
+                }
+                Ok(curve) => {
+                    let value = curve.sell_quote(position.remaining);
+                    let price = value as f64 / position.remaining as f64;
+                    position.peak_price = position.peak_price.max(price);
+                    info!(
+                        "{} worth {} SOL ({:+.2}%)",
+                        position.mint,
+                        lamports_to_sol(value),
+                        position.pnl_percent(price)
+                    );
+
+                    if let Some((reason, token_amount)) =
+                        self.config.strategy.next_exit(&position, price, expired)
+                    {
+                        if let Some(exit) = self.sell(&position, &curve, token_amount, reason).await
+                        {
+                            position.record(exit);
+                            if position.remaining == 0 {
+                                return Some(self.close(position));
+                            }
+                            continue;
+                        }
+                    }
+                }
+                Err(err) => warn!(
+                    "failed to fetch bonding curve of {}: {}",
+                    position.mint, err
+                ),
+            }
+
+            if expired {
+                warn!(
+                    "giving up on selling {}, {} tokens left, {} SOL realised so far",
+                    position.mint,
+                    position.remaining,
+                    lamports_to_sol(position.proceeds)
+                );
+                return None;
+            }
+            self.wait_for_update(&position.mint, &mut updates).await;
+        }
+    }
+
+    async fn current_curve(&self, mint: &Pubkey) -> Result<BondingCurve> {
+        match self.price_feed.as_ref().and_then(|feed| feed.latest(mint)) {
+            Some(update) => Ok(update.curve),
+            None => pump::fetch_bonding_curve(&self.rpc_client, mint).await,
+        }
+    }
+
+    // Wait for the next feed update of the mint, at most PRICE_CHECK_INTERVAL so expiry is still noticed
+    async fn wait_for_update(
+        &self,
+        mint: &Pubkey,
+        updates: &mut Option<broadcast::Receiver<PriceUpdate>>,
+    ) {
+        let Some(receiver) = updates else {
+            tokio::time::sleep(self.config.price_check_interval).await;
+            return;
+        };
+
+        let next = async {
+            loop {
+                match receiver.recv().await {
+                    Ok(update) if update.mint == *mint => return,
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => std::future::pending::<()>().await,
+                }
+            }
+        };
+        let _ = tokio::time::timeout(self.config.price_check_interval, next).await;
+    }
+
+    async fn sell(
+        &self,
+        position: &Position,
+        curve: &BondingCurve,
+        token_amount: u64,
+        reason: ExitReason,
+    ) -> Option<Exit> {
+        let creator = match curve.fee_creator() {
+            Ok(creator) => creator,
+            Err(err) => {
+                warn!("can't sell {}: {}", position.mint, err);
+                return None;
+            }
+        };
+        let expected = curve.sell_quote(token_amount);
+        let min_sol_output = expected * (100 - self.config.sell_slippage.min(100)) / 100;
+        info!(
+            "selling {} of {} on {:?}, expecting at least {} lamports",
+            token_amount, position.mint, reason, min_sol_output
+        );
+
+        let execution = match self
+            .executor
+            .sell(
+                &self.payer,
+                &position.mint,
+                &creator,
+                token_amount,
+                min_sol_output,
+                self.config.max_sell_retries,
+            )
+            .await
+        {
+            Ok(execution) if execution.confirmed => execution,
+            Ok(execution) => {
+                warn!(
+                    "sell of {} did not land: {}",
+                    position.mint,
+                    execution.error.as_deref().unwrap_or("unknown error")
+                );
+                return None;
+            }
+            Err(err) => {
+                warn!("sell of {} failed: {}", position.mint, err);
+                return None;
+            }
+        };
+
+        // What the wallet really received, fees taken off
+        let proceeds = match tracker::fetch_balance_change(
+            &self.rpc_client,
+            &execution.signature,
+            &self.payer.pubkey(),
+            &position.mint,
+        )
+        .await
+        {
+            Ok(change) => change.lamports.max(0) as u64,
+            Err(err) => {
+                warn!(
+                    "no balance change found for the sell of {}, using the quote: {}",
+                    position.mint, err
+                );
+                expected
+            }
+        };
+
+        info!(
+            "sold {} of {} on {:?} for {} SOL, signature {}",
+            token_amount,
+            position.mint,
+            reason,
+            lamports_to_sol(proceeds),
+            execution.signature
+        );
+        Some(Exit {
+            reason,
+            token_amount,
+            proceeds,
+            execution,
+        })
+    }
+
+    fn close(&self, position: Position) -> ClosedPosition {
+        let pnl_lamports = position.proceeds as i64 - position.cost as i64;
+        let closed = ClosedPosition {
+            mint: position.mint,
+            cost: position.cost,
+            proceeds: position.proceeds,
+            pnl_lamports,
+            pnl_percent: if position.cost == 0 {
+                0.0
+            } else {
+                pnl_lamports as f64 / position.cost as f64 * 100.0
+            },
+            exits: position.exits,
+        };
+        info!(
+            "closed {} in {} exit(s) after {:?}: cost {} SOL, proceeds {} SOL, PnL {:+.6} SOL ({:+.2}%)",
+            closed.mint,
+            closed.exits.len(),
+            position.opened_at.elapsed(),
+            lamports_to_sol(closed.cost),
+            lamports_to_sol(closed.proceeds),
+            closed.pnl_lamports as f64 / 1e9,
+            closed.pnl_percent
+        );
+        closed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signature;
+
+    fn strategy(take_profit: f64, trailing_stop: f64, ladder: &str) -> SellStrategy {
+        SellStrategy {
+            take_profit,
+            stop_loss: 20.0,
+            trailing_stop,
+            ladder: parse_ladder(ladder),
+        }
+    }
+
+    // 1000 tokens bought at 1 lamport each
+    fn position() -> Position {
+        Position {
+            mint: Pubkey::new_unique(),
+            token_amount: 1_000,
+            remaining: 1_000,
+            cost: 1_000,
+            proceeds: 0,
+            entry_cost: 1_000,
+            peak_price: 1.0,
+            ladder_filled: 0,
+            exits: Vec::new(),
+            opened_at: Instant::now(),
+        }
+    }
+
+    fn execution() -> ExecutionResult {
+        ExecutionResult {
+            signature: Signature::default(),
+            confirmed: true,
+            attempts: 1,
+            latency: Duration::ZERO,
+            error: None,
+        }
+    }
+
+    fn fill(position: &mut Position, exit: (ExitReason, u64)) {
+        position.record(Exit {
+            reason: exit.0,
+            token_amount: exit.1,
+            proceeds: 0,
+            execution: execution(),
+        });
+    }
+
+    #[test]
+    fn parses_and_sorts_ladders() {
+        assert_eq!(
+            parse_ladder(" 100:30 , 50:30,bad,75:x,200:0"),
+            vec![
+                LadderStep {
+                    gain_percent: 50.0,
+                    sell_percent: 30.0
+                },
+                LadderStep {
+                    gain_percent: 100.0,
+                    sell_percent: 30.0
+                },
+            ]
+        );
+        assert!(parse_ladder("").is_empty());
+    }
+
+    #[test]
+    fn plain_take_profit_and_stop_loss() {
+        let strategy = strategy(40.0, 0.0, "");
+        let position = position();
+        assert_eq!(strategy.next_exit(&position, 1.2, false), None);
+        assert_eq!(
+            strategy.next_exit(&position, 1.5, false),
+            Some((ExitReason::TakeProfit, 1_000))
+        );
+        assert_eq!(
+            strategy.next_exit(&position, 0.75, false),
+            Some((ExitReason::StopLoss, 1_000))
+        );
+        assert_eq!(
+            strategy.next_exit(&position, 1.0, true),
+            Some((ExitReason::Expired, 1_000))
+        );
+    }
+
+    #[test]
+    fn ladder_steps_fire_before_take_profit() {
+        let strategy = strategy(40.0, 0.0, "50:30,100:30");
+        let mut position = position();
+
+        // Past take profit but below the first step: the ladder holds take profit back
+        assert_eq!(strategy.next_exit(&position, 1.45, false), None);
+        let exit = strategy.next_exit(&position, 1.5, false).unwrap();
+        assert_eq!(exit, (ExitReason::Ladder(0), 300));
+        fill(&mut position, exit);
+        assert_eq!(position.remaining, 700);
+
+        assert_eq!(strategy.next_exit(&position, 1.6, false), None);
+        let exit = strategy.next_exit(&position, 2.0, false).unwrap();
+        assert_eq!(exit, (ExitReason::Ladder(1), 300));
+        fill(&mut position, exit);
+
+        // Without a trailing stop, take profit sells the rest once the ladder is done
+        assert_eq!(
+            strategy.next_exit(&position, 2.0, false),
+            Some((ExitReason::TakeProfit, 400))
+        );
+    }
+
+    #[test]
+    fn rest_of_a_ladder_rides_the_trailing_stop() {
+        let strategy = strategy(40.0, 10.0, "50:30,100:30");
+        let mut position = position();
+        fill(&mut position, (ExitReason::Ladder(0), 300));
+        fill(&mut position, (ExitReason::Ladder(1), 300));
+        position.peak_price = 3.0;
+
+        assert_eq!(strategy.next_exit(&position, 2.8, false), None);
+        assert_eq!(
+            strategy.next_exit(&position, 2.7, false),
+            Some((ExitReason::TrailingStop, 400))
+        );
+    }
+
+    #[test]
+    fn ladder_steps_never_sell_more_than_is_left() {
+        let strategy = strategy(0.0, 0.0, "50:80,100:80");
+        let mut position = position();
+        fill(&mut position, (ExitReason::Ladder(0), 800));
+        assert_eq!(
+            strategy.next_exit(&position, 2.0, false),
+            Some((ExitReason::Ladder(1), 200))
+        );
+    }
+
+    #[test]
+    fn strategy_measures_gains_from_what_went_into_the_curve() {
+        // 0.001 SOL bought the tokens, token account rent, fees and a jito tip took the spend to 0.009 SOL
+        let purchase = Purchase {
+            mint: Pubkey::new_unique(),
+            token_amount: 1_000_000,
+            sol_amount: 9_044_280,
+            curve_sol_amount: 1_000_000,
+            execution: execution(),
+        };
+        let position = Position::from(&purchase);
+        assert_eq!(position.entry_price(), 1.0);
+        assert_eq!(position.peak_price, 1.0);
+        assert_eq!(position.cost, 9_044_280);
+
+        // Still worth what was paid in, the stop loss waits for the price itself to drop
+        let strategy = strategy(40.0, 0.0, "");
+        assert_eq!(position.pnl_percent(1.0), 0.0);
+        assert_eq!(strategy.next_exit(&position, 1.0, false), None);
+        assert_eq!(
+            strategy.next_exit(&position, 0.75, false),
+            Some((ExitReason::StopLoss, 1_000_000))
+        );
+    }
+}
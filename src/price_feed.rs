@@ -0,0 +1,120 @@
+use crate::pump::{self, BondingCurve};
+use crate::Result;
+use futures_util::StreamExt;
+use log::{debug, warn};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_sdk::clock::Slot;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PriceUpdate {
+    pub mint: Pubkey,
+    pub curve: BondingCurve,
+    pub slot: Slot,
+}
+
+impl PriceUpdate {
+    // SOL per whole token
+    pub fn price(&self) -> f64 {
+        self.curve.price()
+    }
+}
+
+// Streams bonding curve reserves of watched mints through accountSubscribe on one websocket
+pub struct PriceFeed {
+    client: Arc<PubsubClient>,
+    sender: broadcast::Sender<PriceUpdate>,
+    latest: RwLock<HashMap<Pubkey, PriceUpdate>>,
+    subscriptions: Mutex<HashMap<Pubkey, JoinHandle<()>>>,
+}
+
+impl PriceFeed {
+    pub async fn connect(ws_url: &str) -> Result<Arc<Self>> {
+        let (sender, _) = broadcast::channel(1024);
+        Ok(Arc::new(Self {
+            client: Arc::new(PubsubClient::new(ws_url).await?),
+            sender,
+            latest: RwLock::new(HashMap::new()),
+            subscriptions: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    // Updates of every watched mint
+    pub fn subscribe(&self) -> broadcast::Receiver<PriceUpdate> {
+        self.sender.subscribe()
+    }
+
+    pub fn latest(&self, mint: &Pubkey) -> Option<PriceUpdate> {
+        self.latest.read().unwrap().get(mint).copied()
+    }
+
+    pub fn is_watching(&self, mint: &Pubkey) -> bool {
+        self.subscriptions.lock().unwrap().contains_key(mint)
+    }
+
+    // Start publishing updates for the mint's bonding curve, watching twice is a no-op
+    pub fn watch(self: &Arc<Self>, mint: Pubkey) {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if subscriptions.contains_key(&mint) {
+            return;
+        }
+
+        let feed = self.clone();
+        let handle = tokio::spawn(async move {
+            if let Err(err) = feed.stream(mint).await {
+                warn!("price feed for {} stopped: {}", mint, err);
+            }
+            // Fall back to polling rather than acting on a frozen price
+            feed.subscriptions.lock().unwrap().remove(&mint);
+            feed.latest.write().unwrap().remove(&mint);
+        });
+        subscriptions.insert(mint, handle);
+    }
+
+    pub fn unwatch(&self, mint: &Pubkey) {
+        if let Some(handle) = self.subscriptions.lock().unwrap().remove(mint) {
+            handle.abort();
+        }
+        self.latest.write().unwrap().remove(mint);
+    }
+
+    async fn stream(&self, mint: Pubkey) -> Result<()> {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::processed()),
+            ..RpcAccountInfoConfig::default()
+        };
+        let (mut updates, _unsubscribe) = self
+            .client
+            .account_subscribe(&pump::bonding_curve_address(&mint), Some(config))
+            .await?;
+
+        while let Some(response) = updates.next().await {
+            let Some(curve) = response
+                .value
+                .data
+                .decode()
+                .and_then(|data| BondingCurve::decode(&data))
+            else {
+                debug!("undecodable bonding curve update for {}", mint);
+                continue;
+            };
+            let update = PriceUpdate {
+                mint,
+                curve,
+                slot: response.context.slot,
+            };
+            self.latest.write().unwrap().insert(mint, update);
+            // Nobody listening is fine, the latest value is still cached
+            let _ = self.sender.send(update);
+        }
+        Err("subscription closed".into())
+    }
+}
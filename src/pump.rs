@@ -0,0 +1,352 @@
+use crate::fees::ComputeBudget;
+use crate::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+#[allow(deprecated)]
+use solana_sdk::system_program;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+pub const PUMP_PROGRAM_ID: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
+pub const GLOBAL: Pubkey = pubkey!("4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf");
+pub const FEE_RECIPIENT: Pubkey = pubkey!("CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM");
+pub const EVENT_AUTHORITY: Pubkey = pubkey!("Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1");
+
+const BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+const SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+const BONDING_CURVE_DISCRIMINATOR: [u8; 8] = [23, 183, 248, 55, 96, 216, 172, 96];
+
+// The trader's account in buy and sell instructions
+pub const TRADE_USER_ACCOUNT: usize = 6;
+
+// Trading fee charged by the program on both sides, in basis points
+pub const FEE_BASIS_POINTS: u64 = 100;
+pub const TOKEN_DECIMALS: u8 = 6;
+
+// Tokens sold on the curve before it completes, and how far the virtual reserves sit above the real ones
+pub const INITIAL_REAL_TOKEN_RESERVES: u64 = 793_100_000_000_000;
+pub const VIRTUAL_TOKEN_RESERVES_OFFSET: u64 = 279_900_000_000_000;
+
+// Percentage of the curve's tokens sold, from its virtual token reserves
+pub fn bonding_curve_progress(virtual_token_reserves: u64) -> f64 {
+    let real_token_reserves = virtual_token_reserves.saturating_sub(VIRTUAL_TOKEN_RESERVES_OFFSET);
+    let remaining = real_token_reserves as f64 / INITIAL_REAL_TOKEN_RESERVES as f64;
+    (100.0 * (1.0 - remaining)).clamp(0.0, 100.0)
+}
+
+// PDA holding the bonding curve state of a mint
+pub fn bonding_curve_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &PUMP_PROGRAM_ID).0
+}
+
+// Token account of the bonding curve holding the unsold supply
+pub fn associated_bonding_curve_address(mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(&bonding_curve_address(mint), mint, token_program)
+}
+
+// PDA collecting the creator fee of every curve launched by `creator`
+pub fn creator_vault_address(creator: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"creator-vault", creator.as_ref()], &PUMP_PROGRAM_ID).0
+}
+
+pub fn is_trade_instruction(data: &[u8]) -> bool {
+    data.get(..8).is_some_and(|discriminator| {
+        discriminator == BUY_DISCRIMINATOR || discriminator == SELL_DISCRIMINATOR
+    })
+}
+
+// Buy `token_amount` tokens, paying at most `max_sol_cost` lamports. `creator` is the curve's creator.
+pub fn buy_instruction(
+    user: &Pubkey,
+    mint: &Pubkey,
+    creator: &Pubkey,
+    token_program: &Pubkey,
+    token_amount: u64,
+    max_sol_cost: u64,
+) -> Instruction {
+    let mut data = BUY_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&token_amount.to_le_bytes());
+    data.extend_from_slice(&max_sol_cost.to_le_bytes());
+
+    Instruction {
+        program_id: PUMP_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(GLOBAL, false),
+            AccountMeta::new(FEE_RECIPIENT, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(bonding_curve_address(mint), false),
+            AccountMeta::new(associated_bonding_curve_address(mint, token_program), false),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(user, mint, token_program),
+                false,
+            ),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new(creator_vault_address(creator), false),
+            AccountMeta::new_readonly(EVENT_AUTHORITY, false),
+            AccountMeta::new_readonly(PUMP_PROGRAM_ID, false),
+        ],
+        data,
+    }
+}
+
+// Sell `token_amount` tokens, receiving at least `min_sol_output` lamports. `creator` is the curve's creator.
+pub fn sell_instruction(
+    user: &Pubkey,
+    mint: &Pubkey,
+    creator: &Pubkey,
+    token_program: &Pubkey,
+    token_amount: u64,
+    min_sol_output: u64,
+) -> Instruction {
+    let mut data = SELL_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&token_amount.to_le_bytes());
+    data.extend_from_slice(&min_sol_output.to_le_bytes());
+
+    Instruction {
+        program_id: PUMP_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(GLOBAL, false),
+            AccountMeta::new(FEE_RECIPIENT, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(bonding_curve_address(mint), false),
+            AccountMeta::new(associated_bonding_curve_address(mint, token_program), false),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(user, mint, token_program),
+                false,
+            ),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(creator_vault_address(creator), false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(EVENT_AUTHORITY, false),
+            AccountMeta::new_readonly(PUMP_PROGRAM_ID, false),
+        ],
+        data,
+    }
+}
+
+// Full buy: compute budget, our token account if missing, then the buy itself
+pub fn buy_instructions(
+    user: &Pubkey,
+    mint: &Pubkey,
+    creator: &Pubkey,
+    token_program: &Pubkey,
+    token_amount: u64,
+    max_sol_cost: u64,
+    compute_budget: &ComputeBudget,
+) -> Vec<Instruction> {
+    let mut instructions = compute_budget.instructions();
+    instructions.push(create_associated_token_account_idempotent(
+        user,
+        user,
+        mint,
+        token_program,
+    ));
+    instructions.push(buy_instruction(
+        user,
+        mint,
+        creator,
+        token_program,
+        token_amount,
+        max_sol_cost,
+    ));
+    instructions
+}
+
+pub fn sell_instructions(
+    user: &Pubkey,
+    mint: &Pubkey,
+    creator: &Pubkey,
+    token_program: &Pubkey,
+    token_amount: u64,
+    min_sol_output: u64,
+    compute_budget: &ComputeBudget,
+) -> Vec<Instruction> {
+    let mut instructions = compute_budget.instructions();
+    instructions.push(sell_instruction(
+        user,
+        mint,
+        creator,
+        token_program,
+        token_amount,
+        min_sol_output,
+    ));
+    instructions
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BondingCurve {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+    // Stored after `complete` by curves created since pump.fun added creator fees
+    pub creator: Option<Pubkey>,
+}
+
+impl BondingCurve {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 49 || data[..8] != BONDING_CURVE_DISCRIMINATOR {
+            return None;
+        }
+        let read_u64 =
+            |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+
+        Some(Self {
+            virtual_token_reserves: read_u64(8),
+            virtual_sol_reserves: read_u64(16),
+            real_token_reserves: read_u64(24),
+            real_sol_reserves: read_u64(32),
+            token_total_supply: read_u64(40),
+            complete: data[48] != 0,
+            creator: data
+                .get(49..81)
+                .map(|bytes| Pubkey::new_from_array(bytes.try_into().unwrap())),
+        })
+    }
+
+    // Creator whose vault takes the creator fee, needed by every buy and sell
+    pub fn fee_creator(&self) -> Result<Pubkey> {
+        self.creator
+            .ok_or_else(|| "bonding curve has no creator, it predates creator fees".into())
+    }
+
+    // Raw tokens received when spending `sol_amount` lamports, fee included
+    pub fn buy_quote(&self, sol_amount: u64) -> u64 {
+        if self.complete || self.virtual_sol_reserves == 0 {
+            return 0;
+        }
+        let sol_in = sol_amount as u128 * 10_000 / (10_000 + FEE_BASIS_POINTS as u128);
+        let k = self.virtual_sol_reserves as u128 * self.virtual_token_reserves as u128;
+        let new_token_reserves = k / (self.virtual_sol_reserves as u128 + sol_in) + 1;
+        let tokens = (self.virtual_token_reserves as u128).saturating_sub(new_token_reserves);
+        tokens.min(self.real_token_reserves as u128) as u64
+    }
+
+    // Lamports received when selling `token_amount` raw tokens, after the fee
+    pub fn sell_quote(&self, token_amount: u64) -> u64 {
+        if self.complete || self.virtual_token_reserves == 0 {
+            return 0;
+        }
+        let sol_out = token_amount as u128 * self.virtual_sol_reserves as u128
+            / (self.virtual_token_reserves as u128 + token_amount as u128);
+        let fee = sol_out * FEE_BASIS_POINTS as u128 / 10_000;
+        (sol_out - fee) as u64
+    }
+
+    // SOL per whole token
+    pub fn price(&self) -> f64 {
+        if self.virtual_token_reserves == 0 {
+            return 0.0;
+        }
+        (self.virtual_sol_reserves as f64 / 1e9)
+            / (self.virtual_token_reserves as f64 / 10f64.powi(TOKEN_DECIMALS as i32))
+    }
+
+    pub fn market_cap_sol(&self) -> f64 {
+        self.price() * self.token_total_supply as f64 / 10f64.powi(TOKEN_DECIMALS as i32)
+    }
+}
+
+pub async fn fetch_bonding_curve(rpc_client: &RpcClient, mint: &Pubkey) -> Result<BondingCurve> {
+    let data = rpc_client
+        .get_account_data(&bonding_curve_address(mint))
+        .await?;
+    BondingCurve::decode(&data)
+        .ok_or_else(|| format!("invalid bonding curve account for {}", mint).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve_data(creator: Option<Pubkey>) -> Vec<u8> {
+        let mut data = BONDING_CURVE_DISCRIMINATOR.to_vec();
+        for value in [1u64, 2, 3, 4, 5] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.push(1);
+        if let Some(creator) = creator {
+            data.extend_from_slice(creator.as_ref());
+        }
+        data
+    }
+
+    #[test]
+    fn decodes_the_creator_when_present() {
+        let creator = Pubkey::new_unique();
+        let curve = BondingCurve::decode(&curve_data(Some(creator))).unwrap();
+        assert_eq!(curve.creator, Some(creator));
+        assert_eq!(curve.real_sol_reserves, 4);
+        assert!(curve.complete);
+
+        let legacy = BondingCurve::decode(&curve_data(None)).unwrap();
+        assert_eq!(legacy.creator, None);
+        assert_eq!(BondingCurve::decode(&curve_data(None)[..48]), None);
+    }
+
+    // Accounts in the order of the current IDL, the creator vault after the token program on buys
+    // and before it on sells
+    #[test]
+    fn trade_instructions_pass_the_creator_vault() {
+        let user = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let token_program = spl_token::id();
+        let vault =
+            Pubkey::find_program_address(&[b"creator-vault", creator.as_ref()], &PUMP_PROGRAM_ID).0;
+        let common = [
+            AccountMeta::new_readonly(GLOBAL, false),
+            AccountMeta::new(FEE_RECIPIENT, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(bonding_curve_address(&mint), false),
+            AccountMeta::new(
+                associated_bonding_curve_address(&mint, &token_program),
+                false,
+            ),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(&user, &mint, &token_program),
+                false,
+            ),
+            AccountMeta::new(user, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+        let program = [
+            AccountMeta::new_readonly(EVENT_AUTHORITY, false),
+            AccountMeta::new_readonly(PUMP_PROGRAM_ID, false),
+        ];
+
+        let buy = buy_instruction(&user, &mint, &creator, &token_program, 1, 2);
+        let mut expected = common.to_vec();
+        expected.push(AccountMeta::new_readonly(token_program, false));
+        expected.push(AccountMeta::new(vault, false));
+        expected.extend_from_slice(&program);
+        assert_eq!(buy.accounts, expected);
+        assert_eq!(buy.accounts[TRADE_USER_ACCOUNT].pubkey, user);
+
+        let sell = sell_instruction(&user, &mint, &creator, &token_program, 1, 2);
+        let mut expected = common.to_vec();
+        expected.push(AccountMeta::new(vault, false));
+        expected.push(AccountMeta::new_readonly(token_program, false));
+        expected.extend_from_slice(&program);
+        assert_eq!(sell.accounts, expected);
+        assert_eq!(sell.accounts[TRADE_USER_ACCOUNT].pubkey, user);
+    }
+
+    #[test]
+    fn trading_needs_the_curve_creator() {
+        let creator = Pubkey::new_unique();
+        let curve = BondingCurve::decode(&curve_data(Some(creator))).unwrap();
+        assert_eq!(curve.fee_creator().unwrap(), creator);
+        assert!(BondingCurve::decode(&curve_data(None))
+            .unwrap()
+            .fee_creator()
+            .is_err());
+    }
+}
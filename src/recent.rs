@@ -0,0 +1,475 @@
+use crate::config::env_or;
+use crate::launch::NewToken;
+use crate::offchain::ipfs_path;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Paths on twitter.com / x.com that are not accounts
+const NON_ACCOUNT_PATHS: [&str; 7] = [
+    "i", "intent", "home", "search", "hashtag", "share", "status",
+];
+
+// The lowercased account handle of a twitter link, `@handle` or bare handle
+pub fn twitter_handle(link: &str) -> Option<String> {
+    let link = link.trim();
+    let without_scheme = link
+        .strip_prefix("https://")
+        .or_else(|| link.strip_prefix("http://"))
+        .unwrap_or(link);
+
+    let handle = match without_scheme.split_once('/') {
+        Some((host, path)) => {
+            let host = host
+                .trim_start_matches("www.")
+                .trim_start_matches("mobile.");
+            if host != "twitter.com" && host != "x.com" {
+                return None;
+            }
+            path.split(['/', '?', '#']).next()?
+        }
+        None if without_scheme.contains('.') => return None,
+        None => without_scheme,
+    };
+
+    let handle = handle.trim_start_matches('@').to_lowercase();
+    let valid = !handle.is_empty()
+        && handle.len() <= 15
+        && handle
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    (valid && !NON_ACCOUNT_PATHS.contains(&handle.as_str())).then_some(handle)
+}
+
+// Shorter names and symbols only count as copies when identical after folding
+const NEAR_DUPLICATE_MIN_LENGTH: usize = 5;
+
+// Lowercased with everything but letters and digits dropped, so "Pepe Coin" folds to "pepecoin"
+pub fn fold(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// Levenshtein distance over chars
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+// IPFS images compare by content path, whichever gateway they are linked through
+fn image_key(image: &str) -> String {
+    ipfs_path(image).unwrap_or(image).trim().to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopiedField {
+    Name,
+    Symbol,
+    Image,
+}
+
+impl fmt::Display for CopiedField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Name => write!(f, "name"),
+            Self::Symbol => write!(f, "symbol"),
+            Self::Image => write!(f, "image"),
+        }
+    }
+}
+
+// A field matching an earlier launch, `distance` edits apart after folding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Copycat {
+    pub field: CopiedField,
+    pub original: Pubkey,
+    pub distance: usize,
+}
+
+impl fmt::Display for Copycat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.distance {
+            0 => write!(f, "{} copies {}", self.field, self.original),
+            distance => write!(
+                f,
+                "{} is {} edit(s) from {}",
+                self.field, distance, self.original
+            ),
+        }
+    }
+}
+
+// What a new token shares with launches seen shortly before it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotations {
+    // Handle and the earlier mint that already linked it
+    pub twitter_reused: Option<(String, Pubkey)>,
+    // At most one per field, against the closest and then most recent earlier launch
+    pub copycats: Vec<Copycat>,
+}
+
+impl Annotations {
+    pub fn is_copycat(&self) -> bool {
+        !self.copycats.is_empty()
+    }
+}
+
+struct RecentLaunch {
+    mint: Pubkey,
+    name: Option<String>,
+    symbol: Option<String>,
+    image: Option<String>,
+    twitter: Option<String>,
+    seen_at: Instant,
+}
+
+impl RecentLaunch {
+    fn field(&self, field: CopiedField) -> Option<&String> {
+        match field {
+            CopiedField::Name => self.name.as_ref(),
+            CopiedField::Symbol => self.symbol.as_ref(),
+            CopiedField::Image => self.image.as_ref(),
+        }
+    }
+}
+
+// Index of the creates seen in the last RECENT_LAUNCHES_WINDOW ms,
+// capped at RECENT_LAUNCHES_MAX entries
+pub struct RecentLaunches {
+    window: Duration,
+    max_launches: usize,
+    copycat_distance: usize,
+    launches: Mutex<VecDeque<RecentLaunch>>,
+}
+
+impl RecentLaunches {
+    pub fn new(window: Duration, max_launches: usize) -> Self {
+        Self {
+            window,
+            max_launches,
+            copycat_distance: 0,
+            launches: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    // Names and symbols within this many edits of an earlier one are copies
+    pub fn with_copycat_distance(mut self, copycat_distance: usize) -> Self {
+        self.copycat_distance = copycat_distance;
+        self
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            Duration::from_millis(env_or("RECENT_LAUNCHES_WINDOW", 3_600_000)),
+            env_or("RECENT_LAUNCHES_MAX", 5_000),
+        )
+        .with_copycat_distance(env_or("COPYCAT_MAX_DISTANCE", 1))
+    }
+
+    fn copycat(
+        &self,
+        launches: &VecDeque<RecentLaunch>,
+        mint: &Pubkey,
+        field: CopiedField,
+        value: &str,
+    ) -> Option<Copycat> {
+        let max_distance = match field {
+            CopiedField::Image => 0,
+            _ if value.chars().count() < NEAR_DUPLICATE_MIN_LENGTH => 0,
+            _ => self.copycat_distance,
+        };
+
+        launches
+            .iter()
+            .rev()
+            .filter(|launch| launch.mint != *mint)
+            .filter_map(|launch| {
+                let earlier = launch.field(field)?;
+                if earlier.chars().count().abs_diff(value.chars().count()) > max_distance {
+                    return None;
+                }
+                let distance = edit_distance(earlier, value);
+                (distance <= max_distance).then_some(Copycat {
+                    field,
+                    original: launch.mint,
+                    distance,
+                })
+            })
+            .min_by_key(|copycat| copycat.distance)
+    }
+
+    // Annotate the token against earlier launches, then add it to the index
+    pub fn observe(&self, token: &NewToken) -> Annotations {
+        let offchain = token.offchain.as_ref();
+        let twitter = offchain
+            .and_then(|offchain| offchain.twitter.as_deref())
+            .and_then(twitter_handle);
+        let folded = |text: Option<&str>| text.map(fold).filter(|text| !text.is_empty());
+        let name = folded(
+            token
+                .name()
+                .or_else(|| offchain.and_then(|offchain| offchain.name.as_deref())),
+        );
+        let symbol = folded(
+            token
+                .symbol()
+                .or_else(|| offchain.and_then(|offchain| offchain.symbol.as_deref())),
+        );
+        let image = offchain
+            .and_then(|offchain| offchain.image.as_deref())
+            .map(image_key)
+            .filter(|image| !image.is_empty());
+
+        let mut launches = self.launches.lock().unwrap();
+        while launches
+            .front()
+            .is_some_and(|launch| launch.seen_at.elapsed() >= self.window)
+        {
+            launches.pop_front();
+        }
+
+        let annotations = Annotations {
+            twitter_reused: twitter.as_ref().and_then(|handle| {
+                launches
+                    .iter()
+                    .rev()
+                    .find(|launch| {
+                        launch.mint != token.mint && launch.twitter.as_ref() == Some(handle)
+                    })
+                    .map(|launch| (handle.clone(), launch.mint))
+            }),
+            copycats: [
+                (CopiedField::Name, &name),
+                (CopiedField::Symbol, &symbol),
+                (CopiedField::Image, &image),
+            ]
+            .into_iter()
+            .filter_map(|(field, value)| {
+                self.copycat(&launches, &token.mint, field, value.as_deref()?)
+            })
+            .collect(),
+        };
+
+        if launches.iter().all(|launch| launch.mint != token.mint) {
+            launches.push_back(RecentLaunch {
+                mint: token.mint,
+                name,
+                symbol,
+                image,
+                twitter,
+                seen_at: Instant::now(),
+            });
+            if launches.len() > self.max_launches {
+                launches.pop_front();
+            }
+        }
+        annotations
+    }
+
+    pub fn len(&self) -> usize {
+        self.launches.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::offchain::OffchainMetadata;
+
+    fn token(offchain: OffchainMetadata) -> NewToken {
+        NewToken::new(String::new(), Pubkey::new_unique(), Pubkey::new_unique())
+            .with_offchain(Some(offchain))
+    }
+
+    fn twitter(link: &str) -> NewToken {
+        token(OffchainMetadata {
+            twitter: Some(link.to_string()),
+            ..OffchainMetadata::default()
+        })
+    }
+
+    #[test]
+    fn extracts_twitter_handles() {
+        assert_eq!(
+            twitter_handle("https://x.com/Pepe_Coin"),
+            Some("pepe_coin".to_string())
+        );
+        assert_eq!(
+            twitter_handle("http://www.twitter.com/pepe/status/123?s=20"),
+            Some("pepe".to_string())
+        );
+        assert_eq!(
+            twitter_handle("mobile.twitter.com/pepe"),
+            Some("pepe".to_string())
+        );
+        assert_eq!(twitter_handle("@pepe"), Some("pepe".to_string()));
+        assert_eq!(twitter_handle("pepe"), Some("pepe".to_string()));
+        assert_eq!(twitter_handle("https://x.com/i/communities/1"), None);
+        assert_eq!(twitter_handle("https://x.com/search?q=pepe"), None);
+        assert_eq!(twitter_handle("https://t.me/pepe"), None);
+        assert_eq!(twitter_handle("pepe.fun"), None);
+        assert_eq!(twitter_handle("https://x.com/"), None);
+        assert_eq!(twitter_handle("way_too_long_handle_here"), None);
+    }
+
+    #[test]
+    fn flags_reused_twitter() {
+        let launches = RecentLaunches::new(Duration::from_secs(60), 10);
+        let first = twitter("https://x.com/Pepe");
+        assert_eq!(launches.observe(&first).twitter_reused, None);
+
+        let second = twitter("https://twitter.com/pepe/status/1");
+        assert_eq!(
+            launches.observe(&second).twitter_reused,
+            Some(("pepe".to_string(), first.mint))
+        );
+        assert_eq!(
+            launches
+                .observe(&twitter("https://x.com/other"))
+                .twitter_reused,
+            None
+        );
+
+        // Seeing a token again only flags it against the others, and doesn't index it twice
+        assert_eq!(
+            launches.observe(&first).twitter_reused,
+            Some(("pepe".to_string(), second.mint))
+        );
+        assert_eq!(launches.len(), 3);
+    }
+
+    #[test]
+    fn forgets_launches_outside_the_window() {
+        let launches = RecentLaunches::new(Duration::ZERO, 10);
+        launches.observe(&twitter("pepe"));
+        assert_eq!(launches.observe(&twitter("pepe")).twitter_reused, None);
+
+        let capped = RecentLaunches::new(Duration::from_secs(60), 1);
+        capped.observe(&twitter("pepe"));
+        capped.observe(&twitter("dog"));
+        assert_eq!(capped.len(), 1);
+        assert_eq!(capped.observe(&twitter("pepe")).twitter_reused, None);
+    }
+
+    fn launch(name: &str, symbol: &str, image: Option<&str>) -> NewToken {
+        token(OffchainMetadata {
+            name: Some(name.to_string()),
+            symbol: Some(symbol.to_string()),
+            image: image.map(str::to_string),
+            ..OffchainMetadata::default()
+        })
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("pepe", ""), 4);
+        assert_eq!(edit_distance("pepe", "pepe"), 0);
+        assert_eq!(edit_distance("pepe", "pepa"), 1);
+        assert_eq!(edit_distance("pepe", "peepe"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("flaw", "lawn"), 2);
+        assert_eq!(edit_distance("ñoño", "nono"), 2);
+    }
+
+    #[test]
+    fn folds_case_spaces_and_punctuation() {
+        assert_eq!(fold("Pepe Coin"), "pepecoin");
+        assert_eq!(fold(" $PEPE-2.0! "), "pepe20");
+        assert_eq!(fold("🐸"), "");
+    }
+
+    #[test]
+    fn flags_copied_names_symbols_and_images() {
+        let launches = RecentLaunches::new(Duration::from_secs(60), 10).with_copycat_distance(1);
+        let original = launch("Pepe Coin", "PEPE", Some("https://ipfs.io/ipfs/QmImage"));
+        assert!(!launches.observe(&original).is_copycat());
+
+        let copy = launch(
+            "pepe-coins",
+            "PEPE",
+            Some("https://cf-ipfs.com/ipfs/QmImage"),
+        );
+        assert_eq!(
+            launches.observe(&copy).copycats,
+            vec![
+                Copycat {
+                    field: CopiedField::Name,
+                    original: original.mint,
+                    distance: 1,
+                },
+                Copycat {
+                    field: CopiedField::Symbol,
+                    original: original.mint,
+                    distance: 0,
+                },
+                Copycat {
+                    field: CopiedField::Image,
+                    original: original.mint,
+                    distance: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn short_values_must_match_exactly() {
+        let launches = RecentLaunches::new(Duration::from_secs(60), 10).with_copycat_distance(2);
+        launches.observe(&launch("Dog", "DOG", None));
+        assert!(!launches.observe(&launch("Dig", "DIG", None)).is_copycat());
+
+        let annotations = launches.observe(&launch("Cat", "dog", None));
+        assert_eq!(annotations.copycats.len(), 1);
+        assert_eq!(annotations.copycats[0].field, CopiedField::Symbol);
+    }
+
+    #[test]
+    fn prefers_the_closest_then_latest_launch() {
+        let launches = RecentLaunches::new(Duration::from_secs(60), 10).with_copycat_distance(2);
+        let moonshot = launch("Moonshot", "AAAAA", None);
+        let earlier = launch("Moonshat", "BBBBB", None);
+        let latest = launch("Moonshat", "CCCCC", None);
+        for token in [&moonshot, &earlier, &latest] {
+            launches.observe(token);
+        }
+
+        // All one edit away, the latest wins
+        let copycats = launches
+            .observe(&launch("Moonshit", "YYYYY", None))
+            .copycats;
+        assert_eq!(copycats[0].original, latest.mint);
+        assert_eq!(copycats[0].distance, 1);
+
+        let copycats = launches
+            .observe(&launch("Moonshot!", "ZZZZZ", None))
+            .copycats;
+        assert_eq!(
+            copycats,
+            vec![Copycat {
+                field: CopiedField::Name,
+                original: moonshot.mint,
+                distance: 0,
+            }]
+        );
+    }
+}
@@ -0,0 +1,378 @@
+use crate::config::env_or;
+use crate::pump::PUMP_PROGRAM_ID;
+use crate::Result;
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+};
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use std::fmt;
+
+// Custom errors of the pump program, numbered from Anchor's 6000 offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PumpError {
+    NotAuthorized = 6000,
+    AlreadyInitialized = 6001,
+    TooMuchSolRequired = 6002,
+    TooLittleSolReceived = 6003,
+    MintDoesNotMatchBondingCurve = 6004,
+    BondingCurveComplete = 6005,
+    BondingCurveNotComplete = 6006,
+    NotInitialized = 6007,
+}
+
+impl PumpError {
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            6000 => Some(Self::NotAuthorized),
+            6001 => Some(Self::AlreadyInitialized),
+            6002 => Some(Self::TooMuchSolRequired),
+            6003 => Some(Self::TooLittleSolReceived),
+            6004 => Some(Self::MintDoesNotMatchBondingCurve),
+            6005 => Some(Self::BondingCurveComplete),
+            6006 => Some(Self::BondingCurveNotComplete),
+            6007 => Some(Self::NotInitialized),
+            _ => None,
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::NotAuthorized => {
+                "the given account is not authorized to execute this instruction"
+            }
+            Self::AlreadyInitialized => "the program is already initialized",
+            Self::TooMuchSolRequired => {
+                "slippage: too much SOL required to buy the given amount of tokens"
+            }
+            Self::TooLittleSolReceived => {
+                "slippage: too little SOL received to sell the given amount of tokens"
+            }
+            Self::MintDoesNotMatchBondingCurve => "the mint does not match the bonding curve",
+            Self::BondingCurveComplete => {
+                "the bonding curve has completed and liquidity migrated to raydium"
+            }
+            Self::BondingCurveNotComplete => "the bonding curve has not completed",
+            Self::NotInitialized => "the program is not initialized",
+        }
+    }
+
+    pub fn is_slippage(&self) -> bool {
+        matches!(self, Self::TooMuchSolRequired | Self::TooLittleSolReceived)
+    }
+}
+
+impl fmt::Display for PumpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} ({}): {}", self, *self as u32, self.message())
+    }
+}
+
+// Pump program error behind a transaction error, if the failing instruction belongs to the pump program
+pub fn decode_pump_error(transaction: &Transaction, error: &TransactionError) -> Option<PumpError> {
+    let TransactionError::InstructionError(index, InstructionError::Custom(code)) = error else {
+        return None;
+    };
+    let message = &transaction.message;
+    let instruction = message.instructions.get(*index as usize)?;
+    if message
+        .account_keys
+        .get(instruction.program_id_index as usize)
+        != Some(&PUMP_PROGRAM_ID)
+    {
+        return None;
+    }
+    PumpError::from_code(*code)
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    pub units_consumed: Option<u64>,
+    // Lamport change of the user wallet, negative when SOL is spent
+    pub sol_delta: i64,
+    // Raw token change of the user's token account for the mint
+    pub token_delta: i64,
+    pub error: Option<TransactionError>,
+    pub pump_error: Option<PumpError>,
+    pub logs: Vec<String>,
+}
+
+impl SimulationReport {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+
+    // Readable failure reason, using the pump error name when we know it
+    pub fn failure_reason(&self) -> Option<String> {
+        match (&self.pump_error, &self.error) {
+            (Some(pump_error), _) => Some(pump_error.to_string()),
+            (None, Some(error)) => Some(error.to_string()),
+            (None, None) => None,
+        }
+    }
+}
+
+// Bounds the simulated balance changes must stay within for the trade to be sent
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SlippageLimits {
+    pub min_token_delta: Option<i64>,
+    pub min_sol_delta: Option<i64>,
+}
+
+impl SlippageLimits {
+    // Buying must not cost more than `max_sol_cost`, plus `fee_allowance` lamports for fees and rent
+    pub fn buy(token_amount: u64, max_sol_cost: u64, fee_allowance: u64) -> Self {
+        Self {
+            min_token_delta: Some(token_amount as i64),
+            min_sol_delta: Some(-((max_sol_cost + fee_allowance) as i64)),
+        }
+    }
+
+    // Selling must return at least `min_sol_output` minus `fee_allowance` lamports
+    pub fn sell(min_sol_output: u64, fee_allowance: u64) -> Self {
+        Self {
+            min_token_delta: None,
+            min_sol_delta: Some(min_sol_output as i64 - fee_allowance as i64),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PreflightError {
+    Failed {
+        reason: String,
+        report: SimulationReport,
+    },
+    SlippageExceeded {
+        reason: String,
+        report: SimulationReport,
+    },
+}
+
+impl fmt::Display for PreflightError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Failed { reason, .. } => write!(f, "simulation failed: {}", reason),
+            Self::SlippageExceeded { reason, .. } => {
+                write!(f, "slippage exceeded in simulation: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PreflightError {}
+
+// Whether buys and sells are simulated before they are sent
+pub fn preflight_enabled() -> bool {
+    env_or("PREFLIGHT_SIMULATION", true)
+}
+
+fn token_amount(data: &[u8]) -> i64 {
+    // amount follows the 32 byte mint and 32 byte owner in both token programs
+    data.get(64..72)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()) as i64)
+        .unwrap_or(0)
+}
+
+fn simulated_state(account: &Option<UiAccount>) -> (i64, i64) {
+    match account {
+        Some(account) => (
+            account.lamports as i64,
+            account
+                .data
+                .decode()
+                .map(|data| token_amount(&data))
+                .unwrap_or(0),
+        ),
+        None => (0, 0),
+    }
+}
+
+fn current_state(account: &Option<Account>) -> (i64, i64) {
+    match account {
+        Some(account) => (account.lamports as i64, token_amount(&account.data)),
+        None => (0, 0),
+    }
+}
+
+// Simulate a trade by `user` on `mint` of `token_program` and report compute usage, balance changes and the decoded error
+pub async fn simulate_trade(
+    rpc_client: &RpcClient,
+    transaction: &Transaction,
+    user: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Result<SimulationReport> {
+    let token_account = get_associated_token_address_with_program_id(user, mint, token_program);
+    let before = rpc_client
+        .get_multiple_accounts_with_commitment(
+            &[*user, token_account],
+            CommitmentConfig::processed(),
+        )
+        .await?
+        .value;
+
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(CommitmentConfig::processed()),
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            addresses: vec![user.to_string(), token_account.to_string()],
+        }),
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let result = rpc_client
+        .simulate_transaction_with_config(transaction, config)
+        .await?
+        .value;
+
+    let (sol_before, _) = current_state(&before[0]);
+    let (_, tokens_before) = current_state(&before[1]);
+    let after = result.accounts.unwrap_or_default();
+    let (sol_after, tokens_after) = match (after.first(), after.get(1)) {
+        (Some(wallet), Some(token)) => (simulated_state(wallet).0, simulated_state(token).1),
+        _ => (sol_before, tokens_before),
+    };
+
+    let pump_error = result
+        .err
+        .as_ref()
+        .and_then(|error| decode_pump_error(transaction, error));
+
+    Ok(SimulationReport {
+        units_consumed: result.units_consumed,
+        sol_delta: sol_after - sol_before,
+        token_delta: tokens_after - tokens_before,
+        error: result.err,
+        pump_error,
+        logs: result.logs.unwrap_or_default(),
+    })
+}
+
+// Simulate and decide whether the trade may be sent. Failures and slippage past `limits` abort.
+pub async fn preflight(
+    rpc_client: &RpcClient,
+    transaction: &Transaction,
+    user: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    limits: SlippageLimits,
+) -> Result<SimulationReport> {
+    let report = simulate_trade(rpc_client, transaction, user, mint, token_program).await?;
+
+    if let Some(reason) = report.failure_reason() {
+        let slippage = report
+            .pump_error
+            .map(|error| error.is_slippage())
+            .unwrap_or(false);
+        return Err(Box::new(if slippage {
+            PreflightError::SlippageExceeded { reason, report }
+        } else {
+            PreflightError::Failed { reason, report }
+        }));
+    }
+
+    if let Some(min_token_delta) = limits.min_token_delta {
+        if report.token_delta < min_token_delta {
+            let reason = format!(
+                "token change {} is below {}",
+                report.token_delta, min_token_delta
+            );
+            return Err(Box::new(PreflightError::SlippageExceeded {
+                reason,
+                report,
+            }));
+        }
+    }
+    if let Some(min_sol_delta) = limits.min_sol_delta {
+        if report.sol_delta < min_sol_delta {
+            let reason = format!(
+                "SOL change {} lamports is below {}",
+                report.sol_delta, min_sol_delta
+            );
+            return Err(Box::new(PreflightError::SlippageExceeded {
+                reason,
+                report,
+            }));
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    // Compute budget instruction first, pump instruction second
+    fn transaction() -> Transaction {
+        let payer = Keypair::new();
+        let instructions = [
+            solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(1),
+            Instruction::new_with_bytes(PUMP_PROGRAM_ID, &[], vec![]),
+        ];
+        Transaction::new_with_payer(&instructions, Some(&payer.pubkey()))
+    }
+
+    #[test]
+    fn decodes_pump_errors_of_pump_instructions_only() {
+        let transaction = transaction();
+        let error =
+            |index, code| TransactionError::InstructionError(index, InstructionError::Custom(code));
+
+        assert_eq!(
+            decode_pump_error(&transaction, &error(1, 6002)),
+            Some(PumpError::TooMuchSolRequired)
+        );
+        assert_eq!(
+            decode_pump_error(&transaction, &error(1, 6005)),
+            Some(PumpError::BondingCurveComplete)
+        );
+        // Same code from the compute budget program, an unknown code and an out of range index
+        assert_eq!(decode_pump_error(&transaction, &error(0, 6002)), None);
+        assert_eq!(decode_pump_error(&transaction, &error(1, 42)), None);
+        assert_eq!(decode_pump_error(&transaction, &error(5, 6002)), None);
+        assert_eq!(
+            decode_pump_error(&transaction, &TransactionError::AccountNotFound),
+            None
+        );
+    }
+
+    #[test]
+    fn pump_error_codes_round_trip() {
+        for code in 6000..=6007 {
+            assert_eq!(PumpError::from_code(code).unwrap() as u32, code);
+        }
+        assert_eq!(PumpError::from_code(6008), None);
+        assert!(PumpError::TooLittleSolReceived.is_slippage());
+        assert!(!PumpError::BondingCurveComplete.is_slippage());
+    }
+
+    #[test]
+    fn slippage_limits_include_the_fee_allowance() {
+        let buy = SlippageLimits::buy(1_000, 50_000, 7_000);
+        assert_eq!(buy.min_token_delta, Some(1_000));
+        assert_eq!(buy.min_sol_delta, Some(-57_000));
+
+        let sell = SlippageLimits::sell(40_000, 5_000);
+        assert_eq!(sell.min_token_delta, None);
+        assert_eq!(sell.min_sol_delta, Some(35_000));
+    }
+
+    #[test]
+    fn reads_the_amount_of_token_accounts() {
+        let mut data = vec![0; 165];
+        data[64..72].copy_from_slice(&1_234u64.to_le_bytes());
+        assert_eq!(token_amount(&data), 1_234);
+        assert_eq!(token_amount(&[0; 10]), 0);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::config::env_or;
+use crate::launch::NewToken;
+use crate::Result;
+use arc_swap::ArcSwap;
+use log::{info, warn};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnipeEntries {
+    pub mints: HashSet<Pubkey>,
+    pub creators: HashSet<Pubkey>,
+    // Uppercased glob patterns, `*` matches any run of characters and `?` a single one
+    pub symbols: Vec<String>,
+}
+
+impl SnipeEntries {
+    // One entry per line: `mint:<address>`, `creator:<address>`, `symbol:<pattern>` or a bare mint address.
+    // Blank lines and lines starting with # are ignored.
+    pub fn parse(contents: &str) -> Self {
+        let mut entries = Self::default();
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (kind, value) = line.split_once(':').unwrap_or(("mint", line));
+            let value = value.trim();
+
+            match kind.trim().to_lowercase().as_str() {
+                "mint" => match Pubkey::from_str(value) {
+                    Ok(mint) => {
+                        entries.mints.insert(mint);
+                    }
+                    Err(_) => warn!("invalid mint in snipe list: {}", value),
+                },
+                "creator" => match Pubkey::from_str(value) {
+                    Ok(creator) => {
+                        entries.creators.insert(creator);
+                    }
+                    Err(_) => warn!("invalid creator in snipe list: {}", value),
+                },
+                "symbol" => entries.symbols.push(value.to_uppercase()),
+                _ => warn!("unknown snipe list entry: {}", line),
+            }
+        }
+        entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.mints.len() + self.creators.len() + self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnipeMatch {
+    Mint,
+    Creator,
+    Symbol(String),
+}
+
+impl fmt::Display for SnipeMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Mint => write!(f, "listed mint"),
+            Self::Creator => write!(f, "listed creator"),
+            Self::Symbol(pattern) => write!(f, "symbol matches {}", pattern),
+        }
+    }
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match (pattern.first(), text.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            glob_match(&pattern[1..], text) || (!text.is_empty() && glob_match(pattern, &text[1..]))
+        }
+        (Some('?'), Some(_)) => glob_match(&pattern[1..], &text[1..]),
+        (Some(p), Some(t)) if p == t => glob_match(&pattern[1..], &text[1..]),
+        _ => false,
+    }
+}
+
+// Case-insensitive glob match of a symbol against an uppercased pattern
+pub fn symbol_matches(pattern: &str, symbol: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let symbol: Vec<char> = symbol.to_uppercase().chars().collect();
+    glob_match(&pattern, &symbol)
+}
+
+// Mints, creators and symbol patterns to snipe, reloaded from a file while running
+pub struct SnipeList {
+    path: PathBuf,
+    entries: ArcSwap<SnipeEntries>,
+}
+
+impl SnipeList {
+    pub fn load(path: impl AsRef<Path>) -> Arc<Self> {
+        let list = Arc::new(Self {
+            path: path.as_ref().to_path_buf(),
+            entries: ArcSwap::from_pointee(SnipeEntries::default()),
+        });
+        if let Err(err) = list.reload() {
+            warn!("failed to load snipe list {}: {}", list.path.display(), err);
+        }
+        list
+    }
+
+    // SNIPE_LIST_FILE reloaded every SNIPE_LIST_REFRESH_INTERVAL ms
+    pub fn from_env() -> Arc<Self> {
+        let list = Self::load(env_or("SNIPE_LIST_FILE", "snipe-list.txt".to_string()));
+        list.spawn_reloader(Duration::from_millis(env_or(
+            "SNIPE_LIST_REFRESH_INTERVAL",
+            30_000,
+        )));
+        list
+    }
+
+    pub fn reload(&self) -> Result<()> {
+        let entries = SnipeEntries::parse(&std::fs::read_to_string(&self.path)?);
+        if *self.entries.load_full() != entries {
+            info!(
+                "loaded {} snipe list entries from {}",
+                entries.len(),
+                self.path.display()
+            );
+            self.entries.store(Arc::new(entries));
+        }
+        Ok(())
+    }
+
+    // Reload on an interval. The task stops once the list is dropped.
+    pub fn spawn_reloader(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let list: Weak<Self> = Arc::downgrade(self);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(list) = list.upgrade() else {
+                    break;
+                };
+                if let Err(err) = list.reload() {
+                    warn!(
+                        "failed to reload snipe list {}: {}",
+                        list.path.display(),
+                        err
+                    );
+                }
+            }
+        })
+    }
+
+    pub fn entries(&self) -> Arc<SnipeEntries> {
+        self.entries.load_full()
+    }
+
+    pub fn has_symbol_patterns(&self) -> bool {
+        !self.entries.load().symbols.is_empty()
+    }
+
+    pub fn matches(&self, token: &NewToken, symbol: Option<&str>) -> Option<SnipeMatch> {
+        let entries = self.entries.load();
+        if entries.mints.contains(&token.mint) {
+            return Some(SnipeMatch::Mint);
+        }
+        if entries.creators.contains(&token.creator) {
+            return Some(SnipeMatch::Creator);
+        }
+        let symbol = symbol?;
+        entries
+            .symbols
+            .iter()
+            .find(|pattern| symbol_matches(pattern, symbol))
+            .map(|pattern| SnipeMatch::Symbol(pattern.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_entries() {
+        let mint = Pubkey::new_unique();
+        let bare = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let contents = format!(
+            "# comment\n\nmint:{}\n{}\n CREATOR : {}\nsymbol: pepe*\nmint:nope\nfoo:bar\n",
+            mint, bare, creator
+        );
+        let entries = SnipeEntries::parse(&contents);
+
+        assert_eq!(entries.mints, HashSet::from([mint, bare]));
+        assert_eq!(entries.creators, HashSet::from([creator]));
+        assert_eq!(entries.symbols, vec!["PEPE*".to_string()]);
+        assert_eq!(entries.len(), 4);
+        assert!(SnipeEntries::parse("# only a comment\n").is_empty());
+    }
+
+    #[test]
+    fn globs_symbols() {
+        assert!(symbol_matches("PEPE", "pepe"));
+        assert!(!symbol_matches("PEPE", "PEPE2"));
+        assert!(symbol_matches("PEPE*", "PEPE"));
+        assert!(symbol_matches("PEPE*", "pepecoin"));
+        assert!(symbol_matches("*DOG*", "hotdogs"));
+        assert!(!symbol_matches("*DOG*", "cat"));
+        assert!(symbol_matches("?AT", "cat"));
+        assert!(!symbol_matches("?AT", "at"));
+        assert!(symbol_matches("A*B?C", "axxbyc"));
+        assert!(!symbol_matches("A*B?C", "axxbc"));
+    }
+
+    #[test]
+    fn matches_mint_creator_and_symbol() {
+        let path = std::env::temp_dir().join(format!("snipe-list-{}.txt", Pubkey::new_unique()));
+        let token = NewToken::new(String::new(), Pubkey::new_unique(), Pubkey::new_unique());
+        let other = NewToken::new(String::new(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        std::fs::write(&path, format!("{}\nsymbol:MOON*\n", token.mint)).unwrap();
+        let list = SnipeList::load(&path);
+        assert!(list.has_symbol_patterns());
+        assert_eq!(list.matches(&token, None), Some(SnipeMatch::Mint));
+        assert_eq!(list.matches(&other, None), None);
+        assert_eq!(
+            list.matches(&other, Some("moonshot")),
+            Some(SnipeMatch::Symbol("MOON*".to_string()))
+        );
+
+        std::fs::write(&path, format!("creator:{}\n", other.creator)).unwrap();
+        list.reload().unwrap();
+        assert_eq!(list.matches(&token, None), None);
+        assert_eq!(list.matches(&other, None), Some(SnipeMatch::Creator));
+        assert!(!list.has_symbol_patterns());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,308 @@
+use crate::blacklist::{self, Blacklist, BlacklistKind};
+use crate::config::{env_or, sol_to_lamports};
+use crate::executor::{ExecutionResult, Executor};
+use crate::filters::{FilterDecision, FilterPipeline, FilterSchedule, FilterScheduler};
+use crate::launch::NewToken;
+use crate::metadata;
+use crate::pump;
+use crate::snipe_list::{SnipeList, SnipeMatch};
+use crate::tracker;
+use crate::Result;
+use log::{debug, info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct SniperConfig {
+    // Lamports spent per buy
+    pub quote_amount: u64,
+    // Percent over the quoted cost we are willing to pay
+    pub buy_slippage: u64,
+    pub auto_buy_delay: Duration,
+    pub max_buy_retries: u32,
+    pub one_token_at_a_time: bool,
+}
+
+impl SniperConfig {
+    pub fn from_env() -> Self {
+        let quote_mint = env_or("QUOTE_MINT", "WSOL".to_string());
+        if !quote_mint.eq_ignore_ascii_case("WSOL") && !quote_mint.eq_ignore_ascii_case("SOL") {
+            warn!(
+                "pump.fun only trades against SOL, ignoring QUOTE_MINT={}",
+                quote_mint
+            );
+        }
+
+        Self {
+            quote_amount: sol_to_lamports(env_or("QUOTE_AMOUNT", 0.001)),
+            buy_slippage: env_or("BUY_SLIPPAGE", 20),
+            auto_buy_delay: Duration::from_millis(env_or("AUTO_BUY_DELAY", 0)),
+            max_buy_retries: env_or("MAX_BUY_RETRIES", 10),
+            one_token_at_a_time: env_or("ONE_TOKEN_AT_A_TIME", false),
+        }
+    }
+}
+
+// A buy that landed
+#[derive(Debug, Clone)]
+pub struct Purchase {
+    pub mint: Pubkey,
+    pub token_amount: u64,
+    // Lamports the wallet spent, fees and token account rent included
+    pub sol_amount: u64,
+    // Lamports paid into the bonding curve for the tokens, without fees, tips or rent
+    pub curve_sol_amount: u64,
+    pub execution: ExecutionResult,
+}
+
+// Buys new pump.fun tokens as they are created
+pub struct Sniper {
+    config: SniperConfig,
+    payer: Arc<Keypair>,
+    rpc_client: Arc<RpcClient>,
+    executor: Arc<Executor>,
+    filters: FilterScheduler,
+    snipe_list: Option<Arc<SnipeList>>,
+    blacklist: Option<Arc<Blacklist>>,
+    position_open: AtomicBool,
+}
+
+impl Sniper {
+    pub fn new(
+        config: SniperConfig,
+        payer: Arc<Keypair>,
+        rpc_client: Arc<RpcClient>,
+        executor: Arc<Executor>,
+    ) -> Self {
+        Self {
+            config,
+            payer,
+            rpc_client,
+            executor,
+            filters: FilterScheduler::new(FilterPipeline::new(), FilterSchedule::once()),
+            snipe_list: None,
+            blacklist: None,
+            position_open: AtomicBool::new(false),
+        }
+    }
+
+    // Only buy tokens the scheduler lets through
+    pub fn with_filters(mut self, filters: FilterScheduler) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    // Whether a filter has to wait for the off-chain metadata before the token can be bought
+    pub fn needs_offchain(&self) -> bool {
+        self.filters.pipeline().needs_offchain()
+    }
+
+    // Buy the token after AUTO_BUY_DELAY if it passes the filters. With ONE_TOKEN_AT_A_TIME, tokens are skipped
+    // while a position is open, until `position_closed` is called.
+    pub async fn on_new_token(&self, token: &NewToken) -> Option<Purchase> {
+        if self.config.one_token_at_a_time && self.position_open.load(Ordering::SeqCst) {
+            info!("skipping {}, a position is already open", token.mint);
+            return None;
+        }
+
+        match self.buy(token).await {
+            Ok(purchase) => purchase,
+            Err(err) => {
+                warn!("buy of {} failed: {}", token.mint, err);
+                None
+            }
+        }
+    }
+
+    // Only buy tokens on the snipe list, skipping the filters for them
+    pub fn with_snipe_list(mut self, snipe_list: Arc<SnipeList>) -> Self {
+        self.snipe_list = Some(snipe_list);
+        self
+    }
+
+    // Never buy blacklisted mints, or tokens from blacklisted creators or their funders
+    pub fn with_blacklist(mut self, blacklist: Arc<Blacklist>) -> Self {
+        self.blacklist = Some(blacklist);
+        self
+    }
+
+    pub fn position_closed(&self) {
+        self.position_open.store(false, Ordering::SeqCst);
+    }
+
+    // Symbols missing from the create transaction are only looked up when the list has symbol patterns
+    async fn snipe_list_match(
+        &self,
+        snipe_list: &SnipeList,
+        token: &NewToken,
+    ) -> Result<Option<SnipeMatch>> {
+        if let Some(symbol) = token.symbol() {
+            return Ok(snipe_list.matches(token, Some(symbol)));
+        }
+        if let Some(entry) = snipe_list.matches(token, None) {
+            return Ok(Some(entry));
+        }
+        if !snipe_list.has_symbol_patterns() {
+            return Ok(None);
+        }
+        let metadata = metadata::fetch_metadata(&self.rpc_client, &token.mint).await?;
+        Ok(metadata.and_then(|metadata| snipe_list.matches(token, Some(&metadata.symbol))))
+    }
+
+    // Funding wallets are only looked up when the blacklist has some
+    async fn blacklisted(
+        &self,
+        blacklist: &Blacklist,
+        token: &NewToken,
+    ) -> Result<Option<(BlacklistKind, Pubkey)>> {
+        if let Some(entry) = blacklist.check(token) {
+            return Ok(Some(entry));
+        }
+        if !blacklist.has_funders() {
+            return Ok(None);
+        }
+        let funder = blacklist::funding_wallet(&self.rpc_client, &token.creator).await?;
+        Ok(funder
+            .filter(|funder| blacklist.contains(BlacklistKind::Funder, funder))
+            .map(|funder| (BlacklistKind::Funder, funder)))
+    }
+
+    async fn buy(&self, token: &NewToken) -> Result<Option<Purchase>> {
+        if let Some(blacklist) = &self.blacklist {
+            if let Some((kind, address)) = self.blacklisted(blacklist, token).await? {
+                info!(
+                    "skipping {}, {} {} is blacklisted",
+                    token.mint, kind, address
+                );
+                return Ok(None);
+            }
+        }
+
+        let listed = match &self.snipe_list {
+            Some(snipe_list) => match self.snipe_list_match(snipe_list, token).await? {
+                Some(entry) => {
+                    info!("{} is on the snipe list ({})", token.mint, entry);
+                    true
+                }
+                None => {
+                    debug!("{} is not on the snipe list", token.mint);
+                    return Ok(None);
+                }
+            },
+            None => false,
+        };
+
+        if !self.config.auto_buy_delay.is_zero() {
+            tokio::time::sleep(self.config.auto_buy_delay).await;
+        }
+
+        if !listed && !self.filters.pipeline().is_empty() {
+            match self.filters.decide(&self.rpc_client, token).await {
+                FilterDecision::Buy(report) => info!("{}", report),
+                FilterDecision::Reject { reason, .. } => {
+                    info!("rejected {}: {}", token.mint, reason);
+                    return Ok(None);
+                }
+            }
+        }
+
+        // The slot is only taken once the token passed, so rejected candidates don't block others
+        if self.config.one_token_at_a_time && self.position_open.swap(true, Ordering::SeqCst) {
+            info!("skipping {}, a position is already open", token.mint);
+            return Ok(None);
+        }
+        let purchase = self.execute_buy(token).await;
+        if self.config.one_token_at_a_time && !matches!(purchase, Ok(Some(_))) {
+            self.position_closed();
+        }
+        purchase
+    }
+
+    async fn execute_buy(&self, token: &NewToken) -> Result<Option<Purchase>> {
+        let curve = pump::fetch_bonding_curve(&self.rpc_client, &token.mint).await?;
+        let token_amount = curve.buy_quote(self.config.quote_amount);
+        if token_amount == 0 {
+            info!(
+                "nothing to buy for {}, the bonding curve is complete",
+                token.mint
+            );
+            return Ok(None);
+        }
+        let creator = curve.fee_creator()?;
+        let max_sol_cost = self.config.quote_amount * (100 + self.config.buy_slippage) / 100;
+
+        info!(
+            "buying {} tokens of {} for {} lamports (max {})",
+            token_amount, token.mint, self.config.quote_amount, max_sol_cost
+        );
+        let execution = self
+            .executor
+            .buy(
+                &self.payer,
+                &token.mint,
+                &creator,
+                token_amount,
+                max_sol_cost,
+                self.config.max_buy_retries,
+            )
+            .await?;
+
+        if !execution.confirmed {
+            warn!(
+                "buy of {} did not land after {} attempt(s): {}",
+                token.mint,
+                execution.attempts,
+                execution.error.as_deref().unwrap_or("unknown error")
+            );
+            return Ok(None);
+        }
+
+        // What the buy really cost and got, the quote is only what we asked for
+        let (token_amount, sol_amount, curve_sol_amount) = match tracker::fetch_balance_change(
+            &self.rpc_client,
+            &execution.signature,
+            &self.payer.pubkey(),
+            &token.mint,
+        )
+        .await
+        {
+            Ok(change) if change.tokens > 0 => (
+                change.tokens as u64,
+                (-change.lamports).max(0) as u64,
+                change.curve_lamports.unwrap_or(self.config.quote_amount),
+            ),
+            Ok(_) | Err(_) => {
+                warn!(
+                    "no balance change found for the buy of {}, using the quote",
+                    token.mint
+                );
+                (
+                    token_amount,
+                    self.config.quote_amount,
+                    self.config.quote_amount,
+                )
+            }
+        };
+
+        info!(
+            "bought {} tokens of {} for {} lamports ({} into the curve) in {:?}, signature {}",
+            token_amount,
+            token.mint,
+            sol_amount,
+            curve_sol_amount,
+            execution.latency,
+            execution.signature
+        );
+        Ok(Some(Purchase {
+            mint: token.mint,
+            token_amount,
+            sol_amount,
+            curve_sol_amount,
+            execution,
+        }))
+    }
+}
@@ -0,0 +1,335 @@
+use crate::blockhash::BlockhashCache;
+use crate::events::TradeEvent;
+use crate::Result;
+use log::{info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig};
+use solana_sdk::clock::Slot;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_transaction_status::{UiTransactionEncoding, UiTransactionTokenBalance};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxStatus {
+    Confirmed { slot: Slot },
+    // Landed but the program returned an error, re-sending won't help
+    Failed { slot: Slot, error: TransactionError },
+    // Every attempt ran past its last valid block height without landing
+    Expired,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackedTransaction {
+    pub signature: Signature,
+    pub status: TxStatus,
+    pub attempts: u32,
+    // Time from the first send until the final status was observed
+    pub latency: Duration,
+}
+
+impl TrackedTransaction {
+    pub fn is_confirmed(&self) -> bool {
+        matches!(self.status, TxStatus::Confirmed { .. })
+    }
+}
+
+// Sends transactions and follows their signature until they land or their blockhash expires,
+// re-signing with a fresh blockhash on expiry
+pub struct TransactionTracker {
+    rpc_client: Arc<RpcClient>,
+    blockhash_cache: Option<Arc<BlockhashCache>>,
+    commitment: CommitmentConfig,
+    poll_interval: Duration,
+}
+
+impl TransactionTracker {
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            rpc_client,
+            blockhash_cache: None,
+            commitment: CommitmentConfig::confirmed(),
+            poll_interval: Duration::from_millis(400),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    // Take blockhashes from the cache instead of fetching one per attempt
+    pub fn with_blockhash_cache(mut self, blockhash_cache: Arc<BlockhashCache>) -> Self {
+        self.blockhash_cache = Some(blockhash_cache);
+        self
+    }
+
+    // `build` signs the transaction for the given blockhash and is called again on every retry.
+    // At most `max_retries` attempts are made (and always at least one).
+    pub async fn send_and_confirm<F>(
+        &self,
+        build: F,
+        max_retries: u32,
+    ) -> Result<TrackedTransaction>
+    where
+        F: Fn(Hash) -> Transaction,
+    {
+        let started = Instant::now();
+        let mut signature = Signature::default();
+        let mut attempts = 0;
+        let mut expired_block_height = 0;
+
+        while attempts < max_retries.max(1) {
+            attempts += 1;
+
+            let (blockhash, last_valid_block_height) =
+                self.latest_blockhash(expired_block_height).await?;
+            let transaction = build(blockhash);
+            signature = transaction.signatures[0];
+
+            if let Err(err) = self.send(&transaction).await {
+                warn!("attempt {} failed to send {}: {}", attempts, signature, err);
+                continue;
+            }
+
+            match self.watch(&transaction, last_valid_block_height).await {
+                Some(status) => {
+                    let tracked = TrackedTransaction {
+                        signature,
+                        status,
+                        attempts,
+                        latency: started.elapsed(),
+                    };
+                    info!(
+                        "transaction {} finished as {:?} after {} attempt(s) in {:?}",
+                        signature, tracked.status, attempts, tracked.latency
+                    );
+                    return Ok(tracked);
+                }
+                None => {
+                    warn!("blockhash expired for {}, retrying", signature);
+                    expired_block_height = last_valid_block_height;
+                }
+            }
+        }
+
+        Ok(TrackedTransaction {
+            signature,
+            status: TxStatus::Expired,
+            attempts,
+            latency: started.elapsed(),
+        })
+    }
+
+    // A blockhash valid beyond `expired_block_height`, so a retry never reuses the one that just expired
+    async fn latest_blockhash(&self, expired_block_height: u64) -> Result<(Hash, u64)> {
+        if let Some(cached) = self
+            .blockhash_cache
+            .as_ref()
+            .and_then(|cache| cache.fresh())
+        {
+            if cached.last_valid_block_height > expired_block_height {
+                return Ok((cached.blockhash, cached.last_valid_block_height));
+            }
+        }
+        Ok(self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(self.commitment)
+            .await?)
+    }
+
+    async fn send(&self, transaction: &Transaction) -> Result<Signature> {
+        // We rebroadcast ourselves while watching, so the node shouldn't retry for us
+        let config = RpcSendTransactionConfig {
+            skip_preflight: true,
+            max_retries: Some(0),
+            ..RpcSendTransactionConfig::default()
+        };
+        Ok(self
+            .rpc_client
+            .send_transaction_with_config(transaction, config)
+            .await?)
+    }
+
+    // Wait for a transaction sent some other way (a bundle, a relay) until it lands, or return None
+    // once its blockhash expired and it can't land anymore. Nothing is rebroadcast.
+    pub async fn wait_until_settled(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+    ) -> Option<TxStatus> {
+        self.poll(signature, last_valid_block_height, None).await
+    }
+
+    async fn watch(
+        &self,
+        transaction: &Transaction,
+        last_valid_block_height: u64,
+    ) -> Option<TxStatus> {
+        self.poll(
+            &transaction.signatures[0],
+            last_valid_block_height,
+            Some(transaction),
+        )
+        .await
+    }
+
+    // Poll the signature status until it lands, or return None once the
+    // block height has passed the blockhash's last valid block height.
+    // RPC errors only mean the outcome is unknown for now, the transaction may still land.
+    async fn poll(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+        rebroadcast: Option<&Transaction>,
+    ) -> Option<TxStatus> {
+        loop {
+            // Read the height before the status so a transaction landing right at expiry is still seen
+            let block_height = match self
+                .rpc_client
+                .get_block_height_with_commitment(self.commitment)
+                .await
+            {
+                Ok(block_height) => Some(block_height),
+                Err(err) => {
+                    warn!(
+                        "failed to get the block height watching {}: {}",
+                        signature, err
+                    );
+                    None
+                }
+            };
+            let status = match self.rpc_client.get_signature_statuses(&[*signature]).await {
+                Ok(response) => Some(response.value.into_iter().next().flatten()),
+                Err(err) => {
+                    warn!("failed to get the status of {}: {}", signature, err);
+                    None
+                }
+            };
+
+            if let Some(Some(status)) = &status {
+                if let Some(error) = &status.err {
+                    return Some(TxStatus::Failed {
+                        slot: status.slot,
+                        error: error.clone(),
+                    });
+                }
+                if status.satisfies_commitment(self.commitment) {
+                    return Some(TxStatus::Confirmed { slot: status.slot });
+                }
+            }
+
+            // Only expired when the height is past it and the transaction is known not to be processed,
+            // a processed transaction can still confirm after expiry
+            if let (Some(block_height), Some(None)) = (block_height, &status) {
+                if block_height > last_valid_block_height {
+                    return None;
+                }
+            }
+
+            if let Some(transaction) = rebroadcast {
+                let _ = self.send(transaction).await;
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+// What a landed transaction changed for one wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceChange {
+    // Lamports, fees, tips and rent included
+    pub lamports: i64,
+    // Raw tokens of the traded mint
+    pub tokens: i64,
+    // Lamports the wallet's pump.fun trade of the mint paid into the bonding curve, or took out of it,
+    // from its TradeEvent. None without one.
+    pub curve_lamports: Option<u64>,
+}
+
+// Sum of `owner`'s token balances of `mint`, missing for accounts created or closed in the transaction
+fn token_balance(
+    balances: Option<&Vec<UiTransactionTokenBalance>>,
+    owner: &str,
+    mint: &str,
+) -> i64 {
+    balances
+        .into_iter()
+        .flatten()
+        .filter(|balance| {
+            balance.mint == mint
+                && Option::<&String>::from(balance.owner.as_ref()).map(String::as_str)
+                    == Some(owner)
+        })
+        .filter_map(|balance| balance.ui_token_amount.amount.parse::<i64>().ok())
+        .sum()
+}
+
+// Balance change of `owner` from the confirmed transaction's pre and post balances. The transaction
+// may take a moment to be served after its status confirmed, so it is asked for a few times.
+pub async fn fetch_balance_change(
+    rpc_client: &RpcClient,
+    signature: &Signature,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Result<BalanceChange> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    let mut attempts = 0;
+    let transaction = loop {
+        attempts += 1;
+        match rpc_client
+            .get_transaction_with_config(signature, config)
+            .await
+        {
+            Ok(transaction) => break transaction,
+            Err(err) if attempts >= 5 => return Err(err.into()),
+            Err(_) => tokio::time::sleep(Duration::from_millis(400)).await,
+        }
+    };
+
+    let meta = transaction
+        .transaction
+        .meta
+        .ok_or("transaction has no status meta")?;
+    let decoded = transaction
+        .transaction
+        .transaction
+        .decode()
+        .ok_or("failed to decode transaction")?;
+    let index = decoded
+        .message
+        .static_account_keys()
+        .iter()
+        .position(|key| key == owner)
+        .ok_or_else(|| format!("{} is not part of {}", owner, signature))?;
+
+    let lamports = *meta
+        .post_balances
+        .get(index)
+        .ok_or("missing post balance")? as i64
+        - *meta.pre_balances.get(index).ok_or("missing pre balance")? as i64;
+    let (owner, mint) = (owner.to_string(), mint.to_string());
+    let tokens = token_balance(meta.post_token_balances.as_ref().into(), &owner, &mint)
+        - token_balance(meta.pre_token_balances.as_ref().into(), &owner, &mint);
+    let logs: Option<&Vec<String>> = meta.log_messages.as_ref().into();
+    let curve_lamports = logs
+        .map(|logs| TradeEvent::from_logs(logs))
+        .unwrap_or_default()
+        .into_iter()
+        .find(|event| event.user.to_string() == owner && event.mint.to_string() == mint)
+        .map(|event| event.sol_amount);
+    Ok(BalanceChange {
+        lamports,
+        tokens,
+        curve_lamports,
+    })
+}
@@ -0,0 +1,198 @@
+use crate::config::env_or;
+use crate::copy_trade::CopySizing;
+use crate::watchlist::parse_entry;
+use crate::Result;
+use log::warn;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+// Wallets to follow, keyed by address for constant time lookups of every signer
+#[derive(Debug, Clone, Default)]
+pub struct TrackedWallets {
+    labels: HashMap<Pubkey, String>,
+    // Copy trade sizes set per wallet
+    sizing: HashMap<Pubkey, CopySizing>,
+}
+
+// A copy trade size may follow the label, `0.05sol` or `10%` of the wallet's amount.
+// The unit is required so labels like `whale 100` stay labels.
+fn split_sizing(label: Option<String>) -> (Option<String>, Option<CopySizing>) {
+    let Some(label) = label else {
+        return (None, None);
+    };
+    let (name, size) = label
+        .rsplit_once(char::is_whitespace)
+        .unwrap_or(("", &label));
+    match CopySizing::parse_with_unit(size) {
+        Some(sizing) => {
+            let name = name.trim();
+            ((!name.is_empty()).then(|| name.to_string()), Some(sizing))
+        }
+        None => (Some(label), None),
+    }
+}
+
+impl TrackedWallets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // WATCHED_WALLETS_FILE plus the single WATCHED_WALLET_ADDRESS, if set
+    pub fn from_env() -> Self {
+        let mut wallets = Self::new();
+
+        let file = env_or("WATCHED_WALLETS_FILE", "wallets.txt".to_string());
+        if Path::new(&file).exists() {
+            if let Err(err) = wallets.load_file(&file) {
+                warn!("failed to load wallets {}: {}", file, err);
+            }
+        }
+
+        let address = env_or("WATCHED_WALLET_ADDRESS", String::new());
+        if !address.is_empty() {
+            match Pubkey::from_str(&address) {
+                Ok(wallet) => wallets.add(wallet, None),
+                Err(_) => warn!("invalid WATCHED_WALLET_ADDRESS {}", address),
+            }
+        }
+        wallets
+    }
+
+    // One `<address> <label> [size]` or `<address>:<label> [size]` per line, blank lines and
+    // lines starting with # are ignored
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<usize> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut loaded = 0;
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_entry(line) {
+                Some((wallet, label)) => {
+                    let (label, sizing) = split_sizing(label);
+                    self.add(wallet, label);
+                    if let Some(sizing) = sizing {
+                        self.sizing.insert(wallet, sizing);
+                    }
+                    loaded += 1;
+                }
+                None => warn!("invalid wallet entry: {}", line),
+            }
+        }
+        Ok(loaded)
+    }
+
+    // Unlabelled wallets go by their address
+    pub fn add(&mut self, wallet: Pubkey, label: Option<String>) {
+        let label = label.unwrap_or_else(|| wallet.to_string());
+        self.labels.insert(wallet, label);
+    }
+
+    pub fn label(&self, wallet: &Pubkey) -> Option<&str> {
+        self.labels.get(wallet).map(String::as_str)
+    }
+
+    pub fn sizing(&self, wallet: &Pubkey) -> Option<CopySizing> {
+        self.sizing.get(wallet).copied()
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = &Pubkey> {
+        self.labels.keys()
+    }
+
+    pub fn contains(&self, wallet: &Pubkey) -> bool {
+        self.labels.contains_key(wallet)
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::sol_to_lamports;
+
+    fn split(label: &str) -> (Option<String>, Option<CopySizing>) {
+        split_sizing(Some(label.to_string()))
+    }
+
+    #[test]
+    fn sizes_need_a_unit() {
+        assert_eq!(
+            split("KOL-alice 50%"),
+            (
+                Some("KOL-alice".to_string()),
+                Some(CopySizing::Proportional(0.5))
+            )
+        );
+        assert_eq!(
+            split("KOL bob 0.05SOL"),
+            (
+                Some("KOL bob".to_string()),
+                Some(CopySizing::Fixed(sol_to_lamports(0.05)))
+            )
+        );
+        assert_eq!(split("whale 100"), (Some("whale 100".to_string()), None));
+        assert_eq!(split("25%"), (None, Some(CopySizing::Proportional(0.25))));
+        assert_eq!(split_sizing(None), (None, None));
+    }
+
+    #[test]
+    fn rejects_invalid_sizes() {
+        for label in [
+            "whale infsol",
+            "whale NaN%",
+            "whale 0sol",
+            "whale -5%",
+            "whale sol",
+            "whale 1e-12sol",
+        ] {
+            assert_eq!(split(label), (Some(label.to_string()), None), "{}", label);
+        }
+    }
+
+    #[test]
+    fn copy_size_allows_bare_sol() {
+        assert_eq!("0.01".parse(), Ok(CopySizing::Fixed(sol_to_lamports(0.01))));
+        assert_eq!(" 10% ".parse(), Ok(CopySizing::Proportional(0.1)));
+        assert!("inf".parse::<CopySizing>().is_err());
+        assert!("0".parse::<CopySizing>().is_err());
+        assert!("-1%".parse::<CopySizing>().is_err());
+    }
+
+    #[test]
+    fn loads_wallet_files() {
+        let path = std::env::temp_dir().join(format!("wallets-{}.txt", Pubkey::new_unique()));
+        let (alice, bob, carol) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        std::fs::write(
+            &path,
+            format!(
+                "# wallets\n{} KOL-alice 50%\n\n{}:whale 100\n{}\nnot-an-address\n",
+                alice, bob, carol
+            ),
+        )
+        .unwrap();
+
+        let mut wallets = TrackedWallets::new();
+        assert_eq!(wallets.load_file(&path).unwrap(), 3);
+        assert_eq!(wallets.label(&alice), Some("KOL-alice"));
+        assert_eq!(wallets.sizing(&alice), Some(CopySizing::Proportional(0.5)));
+        assert_eq!(wallets.label(&bob), Some("whale 100"));
+        assert_eq!(wallets.sizing(&bob), None);
+        assert_eq!(wallets.label(&carol), Some(carol.to_string().as_str()));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+use crate::config::env_or;
+use crate::Result;
+use log::warn;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+// `<mint>`, `<mint>:<label>` or `<mint> <label>`
+pub fn parse_entry(entry: &str) -> Option<(Pubkey, Option<String>)> {
+    let entry = entry.trim();
+    let (mint, label) = match entry.split_once(|c: char| c == ':' || c.is_whitespace()) {
+        Some((mint, label)) => (mint, Some(label.trim().to_string())),
+        None => (entry, None),
+    };
+    let mint = Pubkey::from_str(mint.trim()).ok()?;
+    Some((mint, label.filter(|label| !label.is_empty())))
+}
+
+// Shortened mint, for mints watched without a label
+fn default_label(mint: &Pubkey) -> String {
+    let mint = mint.to_string();
+    format!("{}..{}", &mint[..4], &mint[mint.len() - 4..])
+}
+
+// Mints to follow with a label each, changeable while running
+#[derive(Default)]
+pub struct WatchList {
+    mints: RwLock<HashMap<Pubkey, String>>,
+}
+
+impl WatchList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // TOKEN_ADDRESS1, the comma separated TOKEN_ADDRESSES and the lines of WATCH_LIST_FILE
+    pub fn from_env() -> Self {
+        let watch_list = Self::new();
+        watch_list.add_list(&env_or("TOKEN_ADDRESS1", String::new()));
+        watch_list.add_list(&env_or("TOKEN_ADDRESSES", String::new()));
+
+        let file = env_or("WATCH_LIST_FILE", String::new());
+        if !file.is_empty() {
+            if let Err(err) = watch_list.load_file(&file) {
+                warn!("failed to load watch list {}: {}", file, err);
+            }
+        }
+        watch_list
+    }
+
+    // Returns false if the mint was already watched, its label is updated then
+    pub fn add(&self, mint: Pubkey, label: Option<String>) -> bool {
+        let label = label.unwrap_or_else(|| default_label(&mint));
+        self.mints.write().unwrap().insert(mint, label).is_none()
+    }
+
+    pub fn remove(&self, mint: &Pubkey) -> bool {
+        self.mints.write().unwrap().remove(mint).is_some()
+    }
+
+    pub fn add_entry(&self, entry: &str) -> Option<Pubkey> {
+        match parse_entry(entry) {
+            Some((mint, label)) => {
+                self.add(mint, label);
+                Some(mint)
+            }
+            None => {
+                warn!("invalid watch list entry: {}", entry);
+                None
+            }
+        }
+    }
+
+    // Comma separated entries
+    pub fn add_list(&self, list: &str) {
+        for entry in list.split(',').filter(|entry| !entry.trim().is_empty()) {
+            self.add_entry(entry);
+        }
+    }
+
+    // One entry per line, blank lines and lines starting with # are ignored
+    pub fn load_file(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| self.add_entry(line))
+            .count())
+    }
+
+    pub fn label(&self, mint: &Pubkey) -> Option<String> {
+        self.mints.read().unwrap().get(mint).cloned()
+    }
+
+    pub fn contains(&self, mint: &Pubkey) -> bool {
+        self.mints.read().unwrap().contains_key(mint)
+    }
+
+    pub fn entries(&self) -> Vec<(Pubkey, String)> {
+        let mut entries: Vec<_> = self
+            .mints
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(mint, label)| (*mint, label.clone()))
+            .collect();
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+        entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.mints.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{serve_rpc, with_context};
+use monitor::blockhash::BlockhashCache;
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Counts getLatestBlockhash calls, answering each with a new blockhash
+async fn rpc_client(fetches: Arc<AtomicUsize>) -> Arc<RpcClient> {
+    let url = serve_rpc(move |method, _| match method {
+        "getLatestBlockhash" => {
+            let height = fetches.fetch_add(1, Ordering::SeqCst) as u64;
+            with_context(json!({
+                "blockhash": Hash::new_unique().to_string(),
+                "lastValidBlockHeight": 1_000 + height,
+            }))
+        }
+        _ => Value::Null,
+    })
+    .await;
+    Arc::new(RpcClient::new(url))
+}
+
+#[tokio::test]
+async fn serves_fresh_blockhashes_from_the_cache() {
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let rpc_client = rpc_client(fetches.clone()).await;
+    let cache = BlockhashCache::new(Duration::from_secs(60));
+    assert!(cache.get().is_none());
+    assert!(cache.is_stale());
+
+    let fetched = cache.get_or_fetch(&rpc_client).await.unwrap();
+    assert_eq!(fetched.last_valid_block_height, 1_000);
+    let cached = cache.get_or_fetch(&rpc_client).await.unwrap();
+    assert_eq!(cached, fetched);
+    assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    assert!(!cache.is_stale());
+}
+
+#[tokio::test]
+async fn refetches_once_the_blockhash_is_too_old() {
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let rpc_client = rpc_client(fetches.clone()).await;
+    let cache = BlockhashCache::new(Duration::from_millis(20));
+
+    let first = cache.get_or_fetch(&rpc_client).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(40)).await;
+    assert!(cache.is_stale());
+    // Stale entries are still readable, just not fresh
+    assert_eq!(cache.get(), Some(first));
+    assert_eq!(cache.fresh(), None);
+
+    let second = cache.get_or_fetch(&rpc_client).await.unwrap();
+    assert_ne!(second.blockhash, first.blockhash);
+    assert_eq!(fetches.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn refresher_stops_when_the_cache_is_dropped() {
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let rpc_client = rpc_client(fetches.clone()).await;
+    let cache = BlockhashCache::new(Duration::from_secs(60));
+
+    let refresher = cache.spawn_refresher(rpc_client, Duration::from_millis(10));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(fetches.load(Ordering::SeqCst) >= 2);
+    assert!(!cache.is_stale());
+
+    drop(cache);
+    tokio::time::timeout(Duration::from_secs(1), refresher)
+        .await
+        .expect("refresher kept running")
+        .unwrap();
+}
@@ -0,0 +1,142 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json(value: serde_json::Value) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: value.to_string().into_bytes(),
+        }
+    }
+}
+
+// Minimal HTTP/1.1 server on a random local port, answering every request with `handler`.
+// Returns the base url to point clients at.
+pub async fn serve<F>(handler: F) -> String
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                if let Some(request) = read_request(&mut socket).await {
+                    let response = handler(request);
+                    let head = format!(
+                        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        response.status,
+                        reason(response.status),
+                        response.content_type,
+                        response.body.len()
+                    );
+                    let _ = socket.write_all(head.as_bytes()).await;
+                    let _ = socket.write_all(&response.body).await;
+                }
+            });
+        }
+    });
+
+    format!("http://{}", address)
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
+
+async fn read_request(socket: &mut TcpStream) -> Option<Request> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        let read = socket.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let content_length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    while buffer.len() < header_end + content_length {
+        let read = socket.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    Some(Request {
+        method,
+        path,
+        body: buffer[header_end..header_end + content_length].to_vec(),
+    })
+}
+
+// JSON-RPC endpoint answering each call with `handler(method, params)` as its result
+pub async fn serve_rpc<F>(handler: F) -> String
+where
+    F: Fn(&str, &serde_json::Value) -> serde_json::Value + Send + Sync + 'static,
+{
+    serve_rpc_or_error(move |method, params| Ok(handler(method, params))).await
+}
+
+// Like `serve_rpc`, answering `Err(message)` with a JSON-RPC error instead of a result
+pub async fn serve_rpc_or_error<F>(handler: F) -> String
+where
+    F: Fn(&str, &serde_json::Value) -> Result<serde_json::Value, String> + Send + Sync + 'static,
+{
+    serve(move |request| {
+        let call: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        let mut response = serde_json::json!({ "jsonrpc": "2.0", "id": call["id"] });
+        match handler(call["method"].as_str().unwrap_or_default(), &call["params"]) {
+            Ok(result) => response["result"] = result,
+            Err(message) => {
+                response["error"] = serde_json::json!({ "code": -32005, "message": message })
+            }
+        }
+        Response::json(response)
+    })
+    .await
+}
+
+// `{ context, value }` wrapper of RPC responses
+pub fn with_context(value: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({ "context": { "slot": 1 }, "value": value })
+}
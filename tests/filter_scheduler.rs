@@ -0,0 +1,109 @@
+mod common;
+
+use common::{serve_rpc, with_context};
+use monitor::filters::{
+    Filter, FilterContext, FilterDecision, FilterPipeline, FilterResult, FilterSchedule,
+    FilterScheduler,
+};
+use monitor::launch::NewToken;
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Passes or fails following a script, failing once it runs out
+struct Scripted {
+    outcomes: Mutex<VecDeque<bool>>,
+    checks: Arc<AtomicUsize>,
+}
+
+impl Filter for Scripted {
+    fn name(&self) -> &'static str {
+        "scripted"
+    }
+
+    fn check(&self, _context: &FilterContext) -> FilterResult {
+        let check = self.checks.fetch_add(1, Ordering::SeqCst) + 1;
+        match self.outcomes.lock().unwrap().pop_front() {
+            Some(true) => FilterResult::pass(self.name(), format!("check {}", check)),
+            _ => FilterResult::fail(self.name(), format!("check {}", check)),
+        }
+    }
+}
+
+// Accounts the filter context fetches, none of which exist
+async fn rpc_client() -> RpcClient {
+    let url = serve_rpc(|method, _| match method {
+        "getMultipleAccounts" => with_context(json!([null, null, null])),
+        _ => Value::Null,
+    })
+    .await;
+    RpcClient::new(url)
+}
+
+fn scheduler(outcomes: &[bool], consecutive_matches: u32) -> (FilterScheduler, Arc<AtomicUsize>) {
+    let checks = Arc::new(AtomicUsize::new(0));
+    let pipeline = FilterPipeline::new().with(Scripted {
+        outcomes: Mutex::new(outcomes.iter().copied().collect()),
+        checks: checks.clone(),
+    });
+    let schedule = FilterSchedule {
+        interval: Duration::from_millis(10),
+        duration: Duration::from_millis(500),
+        consecutive_matches,
+    };
+    (FilterScheduler::new(pipeline, schedule), checks)
+}
+
+fn token() -> NewToken {
+    NewToken::new(String::new(), Pubkey::new_unique(), Pubkey::new_unique())
+}
+
+#[tokio::test]
+async fn buys_after_consecutive_matches() {
+    let (scheduler, checks) = scheduler(&[true, false, true, true, true, true], 3);
+    let decision = scheduler.decide(&rpc_client().await, &token()).await;
+
+    match decision {
+        FilterDecision::Buy(report) => {
+            assert!(report.passed());
+            assert_eq!(report.results[0].reason, "check 5");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    // A failure resets the count, so the first pass doesn't count
+    assert_eq!(checks.load(Ordering::SeqCst), 5);
+}
+
+#[tokio::test]
+async fn rejects_when_the_window_runs_out() {
+    let (scheduler, checks) = scheduler(&[true, true, false], 3);
+    let decision = scheduler.decide(&rpc_client().await, &token()).await;
+
+    match decision {
+        FilterDecision::Reject { reason, report } => {
+            assert!(reason.starts_with("scripted: check"), "{}", reason);
+            assert!(!report.unwrap().passed());
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    // Kept checking until the end of the window
+    assert!(checks.load(Ordering::SeqCst) > 10);
+}
+
+#[tokio::test]
+async fn once_checks_a_single_time() {
+    let checks = Arc::new(AtomicUsize::new(0));
+    let pipeline = FilterPipeline::new().with(Scripted {
+        outcomes: Mutex::new(VecDeque::from([false, true])),
+        checks: checks.clone(),
+    });
+    let scheduler = FilterScheduler::new(pipeline, FilterSchedule::once());
+    let decision = scheduler.decide(&rpc_client().await, &token()).await;
+
+    assert!(matches!(decision, FilterDecision::Reject { .. }));
+    assert_eq!(checks.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,276 @@
+mod common;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use common::{serve, Request, Response};
+use monitor::jito::{
+    buy_bundle, random_tip_account, BlockEngineClient, BundleBuilder, BundleOutcome,
+    JITO_TIP_ACCOUNTS, MAX_BUNDLE_SIZE,
+};
+use monitor::pump::PUMP_PROGRAM_ID;
+use serde_json::{json, Value};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+#[allow(deprecated)]
+use solana_sdk::system_instruction::SystemInstruction;
+#[allow(deprecated)]
+use solana_sdk::system_program;
+use solana_sdk::transaction::Transaction;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Tip transfers in the transaction as (tip account, lamports)
+fn tips(transaction: &Transaction) -> Vec<(Pubkey, u64)> {
+    let keys = &transaction.message.account_keys;
+    transaction
+        .message
+        .instructions
+        .iter()
+        .filter(|instruction| keys[instruction.program_id_index as usize] == system_program::id())
+        .filter_map(|instruction| {
+            match bincode::deserialize::<SystemInstruction>(&instruction.data).ok()? {
+                SystemInstruction::Transfer { lamports } => {
+                    Some((keys[instruction.accounts[1] as usize], lamports))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+// Block engine stand-in: records submitted bundles and reports a bundle as pending
+// for the first `pending_polls` status requests, then as landed in slot 4242.
+async fn mock_block_engine(pending_polls: usize) -> (String, Arc<Mutex<Vec<Vec<Transaction>>>>) {
+    let bundles = Arc::new(Mutex::new(Vec::new()));
+    let polls = AtomicUsize::new(0);
+    let received = bundles.clone();
+
+    let url = serve(move |request: Request| {
+        assert_eq!(request.method, "POST");
+        let body: Value = serde_json::from_slice(&request.body).unwrap();
+        let id = body["id"].clone();
+
+        let result = match body["method"].as_str().unwrap() {
+            "sendBundle" => {
+                assert_eq!(request.path, "/api/v1/bundles");
+                assert_eq!(body["params"][1]["encoding"], "base64");
+                let transactions = body["params"][0]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|encoded| {
+                        let bytes = STANDARD.decode(encoded.as_str().unwrap()).unwrap();
+                        bincode::deserialize::<Transaction>(&bytes).unwrap()
+                    })
+                    .collect();
+                received.lock().unwrap().push(transactions);
+                json!("bundle-1")
+            }
+            "getInflightBundleStatuses" => {
+                let status = if polls.fetch_add(1, Ordering::SeqCst) < pending_polls {
+                    json!({ "bundle_id": "bundle-1", "status": "Pending", "landed_slot": null })
+                } else {
+                    json!({ "bundle_id": "bundle-1", "status": "Landed", "landed_slot": 4242 })
+                };
+                json!({ "context": { "slot": 4300 }, "value": [status] })
+            }
+            "getBundleStatuses" => json!({
+                "context": { "slot": 4300 },
+                "value": [
+                    {
+                        "bundle_id": "bundle-1",
+                        "transactions": ["sig-1"],
+                        "slot": 4242,
+                        "confirmation_status": "confirmed",
+                        "err": { "Ok": null }
+                    },
+                    null
+                ]
+            }),
+            "getTipAccounts" => json!(JITO_TIP_ACCOUNTS
+                .iter()
+                .map(|key| key.to_string())
+                .collect::<Vec<_>>()),
+            _ => {
+                return Response::json(json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": -32601, "message": "Method not found" }
+                }))
+            }
+        };
+
+        Response::json(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    })
+    .await;
+
+    (url, bundles)
+}
+
+#[test]
+fn tip_accounts_rotate() {
+    let picked: HashSet<Pubkey> = (0..200).map(|_| random_tip_account()).collect();
+    assert!(picked
+        .iter()
+        .all(|account| JITO_TIP_ACCOUNTS.contains(account)));
+    assert!(picked.len() > 1);
+}
+
+#[test]
+fn buy_bundle_tips_in_the_buy_transaction() {
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let bundle = buy_bundle(
+        &payer,
+        &mint,
+        &Pubkey::new_unique(),
+        &spl_token::id(),
+        1_000_000,
+        50_000_000,
+        10_000,
+        Hash::new_unique(),
+    )
+    .unwrap();
+
+    assert_eq!(bundle.len(), 1);
+    let transaction = &bundle[0];
+    transaction.verify().unwrap();
+    assert!(transaction.message.account_keys.contains(&PUMP_PROGRAM_ID));
+
+    let tips = tips(transaction);
+    assert_eq!(tips.len(), 1);
+    assert!(JITO_TIP_ACCOUNTS.contains(&tips[0].0));
+    assert_eq!(tips[0].1, 10_000);
+}
+
+#[test]
+fn buy_bundle_uses_the_token_program_of_the_mint() {
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let bundle = buy_bundle(
+        &payer,
+        &mint,
+        &Pubkey::new_unique(),
+        &spl_token_2022::id(),
+        1_000_000,
+        50_000_000,
+        10_000,
+        Hash::new_unique(),
+    )
+    .unwrap();
+
+    let account_keys = &bundle[0].message.account_keys;
+    assert!(account_keys.contains(&spl_token_2022::id()));
+    assert!(!account_keys.contains(&spl_token::id()));
+    let token_account = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &payer.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    assert!(account_keys.contains(&token_account));
+}
+
+#[test]
+fn bundle_builder_tips_only_the_last_transaction() {
+    let payer = Keypair::new();
+    let other = Pubkey::new_unique();
+    let transfer =
+        |lamports| solana_sdk::system_instruction::transfer(&payer.pubkey(), &other, lamports);
+
+    let bundle = BundleBuilder::new(&payer, Hash::new_unique())
+        .add_transaction(vec![transfer(1)])
+        .add_transaction(vec![transfer(2)])
+        .add_transaction(vec![transfer(3)])
+        .build(5_000)
+        .unwrap();
+
+    assert_eq!(bundle.len(), 3);
+    assert!(bundle
+        .iter()
+        .all(|transaction| transaction.verify().is_ok()));
+    assert_eq!(tips(&bundle[0]).len(), 1);
+    assert_eq!(tips(&bundle[2]).len(), 2);
+    assert!(JITO_TIP_ACCOUNTS.contains(&tips(&bundle[2])[1].0));
+}
+
+#[test]
+fn bundle_builder_rejects_empty_and_oversized_bundles() {
+    let payer = Keypair::new();
+    assert!(BundleBuilder::new(&payer, Hash::new_unique())
+        .build(1)
+        .is_err());
+
+    let mut builder = BundleBuilder::new(&payer, Hash::new_unique());
+    for _ in 0..=MAX_BUNDLE_SIZE {
+        builder = builder.add_transaction(vec![]);
+    }
+    assert!(builder.build(1).is_err());
+}
+
+#[tokio::test]
+async fn send_and_poll_bundle() {
+    let (url, bundles) = mock_block_engine(2).await;
+    let client = BlockEngineClient::new(&url);
+
+    let payer = Keypair::new();
+    let bundle = buy_bundle(
+        &payer,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &spl_token::id(),
+        1,
+        2,
+        3,
+        Hash::new_unique(),
+    )
+    .unwrap();
+    let bundle_id = client.send_bundle(&bundle).await.unwrap();
+    assert_eq!(bundle_id, "bundle-1");
+    assert_eq!(bundles.lock().unwrap()[0], bundle);
+
+    let outcome = client
+        .wait_for_bundle(
+            &bundle_id,
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+    assert_eq!(outcome, BundleOutcome::Landed { slot: 4242 });
+}
+
+#[tokio::test]
+async fn wait_for_bundle_times_out() {
+    let (url, _) = mock_block_engine(usize::MAX).await;
+    let client = BlockEngineClient::new(&url);
+
+    let outcome = client
+        .wait_for_bundle(
+            "bundle-1",
+            Duration::from_millis(10),
+            Duration::from_millis(50),
+        )
+        .await
+        .unwrap();
+    assert_eq!(outcome, BundleOutcome::TimedOut);
+}
+
+#[tokio::test]
+async fn bundle_statuses_and_tip_accounts() {
+    let (url, _) = mock_block_engine(0).await;
+    let client = BlockEngineClient::new(&url);
+
+    let statuses = client
+        .get_bundle_statuses(&["bundle-1".to_string(), "unknown".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(statuses.len(), 2);
+    assert_eq!(statuses[0].as_ref().unwrap().slot, 4242);
+    assert!(statuses[1].is_none());
+
+    let accounts = client.get_tip_accounts().await.unwrap();
+    assert_eq!(accounts, JITO_TIP_ACCOUNTS.to_vec());
+}
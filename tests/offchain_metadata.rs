@@ -0,0 +1,214 @@
+mod common;
+
+use common::{serve, Request, Response};
+use monitor::offchain::{MetadataResolver, OffchainMetadata};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const CID: &str = "QmeSzchzEPqCU1jwTnsipwcBAeH7S4bmmvBbmoY3pFf7hb";
+
+fn token_json() -> serde_json::Value {
+    json!({
+        "name": "Pepe Coin",
+        "symbol": "PEPE",
+        "description": "the frog",
+        "image": "https://cf-ipfs.com/ipfs/QmImage",
+        "showName": true,
+        "createdOn": "https://pump.fun",
+        "twitter": "https://x.com/pepe",
+        "telegram": "https://t.me/pepe",
+        "website": "https://pepe.example"
+    })
+}
+
+fn resolver(gateways: Vec<String>) -> MetadataResolver {
+    MetadataResolver::new(gateways, Duration::from_millis(500), 4096)
+}
+
+// Fresh cache directory per test
+fn cache_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("monitor-metadata-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn ipfs_uris_go_through_each_gateway_then_the_original() {
+    let resolver = resolver(vec![
+        "https://a.example/ipfs".to_string(),
+        "https://b.example/ipfs/".to_string(),
+    ]);
+
+    assert_eq!(
+        resolver.candidate_urls(&format!("ipfs://{}", CID)),
+        vec![
+            format!("https://a.example/ipfs/{}", CID),
+            format!("https://b.example/ipfs/{}", CID),
+        ]
+    );
+    assert_eq!(
+        resolver.candidate_urls(&format!("https://ipfs.io/ipfs/{}", CID)),
+        vec![
+            format!("https://a.example/ipfs/{}", CID),
+            format!("https://b.example/ipfs/{}", CID),
+            format!("https://ipfs.io/ipfs/{}", CID),
+        ]
+    );
+    assert_eq!(
+        resolver.candidate_urls("https://arweave.net/abc"),
+        vec!["https://arweave.net/abc".to_string()]
+    );
+    assert!(resolver
+        .candidate_urls("data:application/json,{}")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn resolves_fields_through_the_gateway() {
+    let paths = Arc::new(Mutex::new(Vec::new()));
+    let seen = paths.clone();
+    let url = serve(move |request: Request| {
+        seen.lock().unwrap().push(request.path);
+        Response::json(token_json())
+    })
+    .await;
+
+    let metadata = resolver(vec![format!("{}/ipfs/", url)])
+        .resolve(&format!("https://ipfs.io/ipfs/{}", CID))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        metadata,
+        OffchainMetadata {
+            name: Some("Pepe Coin".to_string()),
+            symbol: Some("PEPE".to_string()),
+            description: Some("the frog".to_string()),
+            image: Some("https://cf-ipfs.com/ipfs/QmImage".to_string()),
+            twitter: Some("https://x.com/pepe".to_string()),
+            telegram: Some("https://t.me/pepe".to_string()),
+            website: Some("https://pepe.example".to_string()),
+        }
+    );
+    assert!(metadata.has_socials());
+    assert_eq!(*paths.lock().unwrap(), vec![format!("/ipfs/{}", CID)]);
+}
+
+#[tokio::test]
+async fn missing_fields_are_none() {
+    let url = serve(|_| Response::json(json!({ "name": "Bare", "symbol": "BARE" }))).await;
+
+    let metadata = resolver(vec![])
+        .resolve(&format!("{}/bare.json", url))
+        .await
+        .unwrap();
+
+    assert_eq!(metadata.name.as_deref(), Some("Bare"));
+    assert_eq!(metadata.image, None);
+    assert!(!metadata.has_socials());
+}
+
+#[tokio::test]
+async fn falls_back_to_the_next_gateway() {
+    let failing = serve(|_| Response {
+        status: 500,
+        content_type: "text/plain",
+        body: b"gateway error".to_vec(),
+    })
+    .await;
+    let working = serve(|_| Response::json(token_json())).await;
+
+    let metadata = resolver(vec![
+        format!("{}/ipfs/", failing),
+        format!("{}/ipfs/", working),
+    ])
+    .resolve(&format!("ipfs://{}", CID))
+    .await
+    .unwrap();
+
+    assert_eq!(metadata.symbol.as_deref(), Some("PEPE"));
+}
+
+#[tokio::test]
+async fn rejects_oversized_documents() {
+    let url = serve(|_| Response::json(json!({ "description": "x".repeat(10_000) }))).await;
+
+    let result = resolver(vec![format!("{}/ipfs/", url)])
+        .resolve(&format!("ipfs://{}", CID))
+        .await;
+
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("larger than 4096 bytes"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn gives_up_on_slow_gateways() {
+    let url = serve(|_| {
+        std::thread::sleep(Duration::from_secs(2));
+        Response::json(token_json())
+    })
+    .await;
+
+    let started = std::time::Instant::now();
+    let result = resolver(vec![format!("{}/ipfs/", url)])
+        .resolve(&format!("ipfs://{}", CID))
+        .await;
+
+    assert!(result.is_err());
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn caches_resolved_metadata_on_disk() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let url = serve(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Response::json(token_json())
+    })
+    .await;
+    let dir = cache_dir("cache");
+    let uri = format!("ipfs://{}", CID);
+
+    let first = resolver(vec![format!("{}/ipfs/", url)]).with_cache_dir(&dir);
+    let resolved = first.resolve(&uri).await.unwrap();
+    assert_eq!(first.resolve(&uri).await.unwrap(), resolved);
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    // A new resolver reads the cache without any gateway reachable
+    let offline = resolver(vec![]).with_cache_dir(&dir);
+    assert_eq!(offline.resolve(&uri).await.unwrap(), resolved);
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn races_the_gateways() {
+    let slow = serve(|_| {
+        std::thread::sleep(Duration::from_millis(400));
+        Response::json(json!({ "symbol": "SLOW" }))
+    })
+    .await;
+    let fast = serve(|_| Response::json(token_json())).await;
+
+    let started = std::time::Instant::now();
+    let metadata = MetadataResolver::new(
+        vec![format!("{}/ipfs/", slow), format!("{}/ipfs/", fast)],
+        Duration::from_secs(2),
+        4096,
+    )
+    .resolve(&format!("ipfs://{}", CID))
+    .await
+    .unwrap();
+
+    // The first gateway in the list is still answering
+    assert_eq!(metadata.symbol.as_deref(), Some("PEPE"));
+    assert!(started.elapsed() < Duration::from_millis(400));
+}
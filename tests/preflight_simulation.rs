@@ -0,0 +1,167 @@
+mod common;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use common::{serve_rpc, with_context};
+use monitor::fees::ComputeBudget;
+use monitor::pump;
+use monitor::simulation::{preflight, PreflightError, PumpError, SlippageLimits};
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+#[allow(deprecated)]
+use solana_sdk::system_program;
+use solana_sdk::transaction::Transaction;
+
+const BALANCE: u64 = 1_000_000_000;
+
+fn account(lamports: u64, owner: &Pubkey, data: &[u8]) -> Value {
+    json!({
+        "lamports": lamports,
+        "data": [STANDARD.encode(data), "base64"],
+        "owner": owner.to_string(),
+        "executable": false,
+        "rentEpoch": 0,
+        "space": data.len(),
+    })
+}
+
+fn token_account(amount: u64) -> Value {
+    let mut data = vec![0; 165];
+    data[64..72].copy_from_slice(&amount.to_le_bytes());
+    account(2_039_280, &spl_token::id(), &data)
+}
+
+// The wallet holds BALANCE and no token account before the trade, simulation ends with
+// `lamports` in the wallet and `tokens` in its token account, failing with `err`
+async fn rpc_client(lamports: u64, tokens: u64, err: Value) -> RpcClient {
+    let url = serve_rpc(move |method, _| match method {
+        "getMultipleAccounts" => {
+            with_context(json!([account(BALANCE, &system_program::id(), &[]), null]))
+        }
+        "simulateTransaction" => with_context(json!({
+            "err": err,
+            "logs": ["Program log: Instruction: Buy"],
+            "accounts": [
+                account(lamports, &system_program::id(), &[]),
+                token_account(tokens),
+            ],
+            "unitsConsumed": 42_000,
+        })),
+        _ => Value::Null,
+    })
+    .await;
+    RpcClient::new(url)
+}
+
+// Compute budget, token account creation, then the buy at index 3
+fn buy(payer: &Keypair, mint: &Pubkey) -> Transaction {
+    let instructions = pump::buy_instructions(
+        &payer.pubkey(),
+        mint,
+        &Pubkey::new_unique(),
+        &spl_token::id(),
+        1_000,
+        60_000,
+        &ComputeBudget {
+            unit_limit: 100_000,
+            unit_price: 0,
+        },
+    );
+    Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        Hash::new_unique(),
+    )
+}
+
+async fn run(lamports: u64, tokens: u64, err: Value) -> monitor::Result<()> {
+    let rpc_client = rpc_client(lamports, tokens, err).await;
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let limits = SlippageLimits::buy(1_000, 60_000, 10_000);
+    let report = preflight(
+        &rpc_client,
+        &buy(&payer, &mint),
+        &payer.pubkey(),
+        &mint,
+        &spl_token::id(),
+        limits,
+    )
+    .await?;
+
+    assert!(report.succeeded());
+    assert_eq!(report.units_consumed, Some(42_000));
+    assert_eq!(report.sol_delta, lamports as i64 - BALANCE as i64);
+    assert_eq!(report.token_delta, tokens as i64);
+    Ok(())
+}
+
+fn preflight_error(err: monitor::Error) -> PreflightError {
+    *err.downcast::<PreflightError>().unwrap()
+}
+
+#[tokio::test]
+async fn passes_trades_within_the_limits() {
+    run(BALANCE - 65_000, 1_000, Value::Null).await.unwrap();
+}
+
+#[tokio::test]
+async fn rejects_too_few_tokens() {
+    let err = run(BALANCE - 65_000, 999, Value::Null).await.unwrap_err();
+    match preflight_error(err) {
+        PreflightError::SlippageExceeded { reason, report } => {
+            assert!(reason.contains("token change 999"), "{}", reason);
+            assert_eq!(report.token_delta, 999);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn rejects_spending_past_the_fee_allowance() {
+    let err = run(BALANCE - 70_001, 1_000, Value::Null).await.unwrap_err();
+    assert!(matches!(
+        preflight_error(err),
+        PreflightError::SlippageExceeded { .. }
+    ));
+}
+
+#[tokio::test]
+async fn decodes_pump_slippage_errors() {
+    let err = run(
+        BALANCE,
+        0,
+        json!({ "InstructionError": [3, { "Custom": 6002 }] }),
+    )
+    .await
+    .unwrap_err();
+    match preflight_error(err) {
+        PreflightError::SlippageExceeded { report, .. } => {
+            assert_eq!(report.pump_error, Some(PumpError::TooMuchSolRequired));
+            assert!(!report.succeeded());
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn fails_on_other_errors() {
+    let err = run(
+        BALANCE,
+        0,
+        json!({ "InstructionError": [3, { "Custom": 6005 }] }),
+    )
+    .await
+    .unwrap_err();
+    match preflight_error(err) {
+        PreflightError::Failed { reason, report } => {
+            assert_eq!(report.pump_error, Some(PumpError::BondingCurveComplete));
+            assert!(reason.contains("BondingCurveComplete"), "{}", reason);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
@@ -0,0 +1,317 @@
+mod common;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use common::{serve_rpc, serve_rpc_or_error, with_context};
+use monitor::pump::PUMP_PROGRAM_ID;
+use monitor::tracker::{TransactionTracker, TxStatus};
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// The signature of the base64 transaction passed to sendTransaction
+fn sent_signature(params: &Value) -> String {
+    let data = STANDARD.decode(params[0].as_str().unwrap()).unwrap();
+    let transaction: Transaction = bincode::deserialize(&data).unwrap();
+    transaction.signatures[0].to_string()
+}
+
+fn blockhash_response(blockhash: Hash, last_valid_block_height: u64) -> Value {
+    with_context(json!({
+        "blockhash": blockhash.to_string(),
+        "lastValidBlockHeight": last_valid_block_height,
+    }))
+}
+
+fn status_response(status: Value) -> Value {
+    with_context(json!([status]))
+}
+
+fn tracker(url: String) -> TransactionTracker {
+    TransactionTracker::new(Arc::new(RpcClient::new(url)))
+        .with_poll_interval(Duration::from_millis(10))
+}
+
+fn noop_transaction(payer: &Keypair, blockhash: Hash) -> Transaction {
+    let instruction =
+        solana_sdk::instruction::Instruction::new_with_bytes(spl_token::id(), &[], vec![]);
+    Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[payer], blockhash)
+}
+
+#[tokio::test]
+async fn confirms_a_landed_transaction() {
+    let sends = Arc::new(AtomicUsize::new(0));
+    let polls = Arc::new(AtomicUsize::new(0));
+    let (sends_seen, polls_seen) = (sends.clone(), polls.clone());
+    let url = serve_rpc(move |method, params| match method {
+        "getLatestBlockhash" => blockhash_response(Hash::new_unique(), 100),
+        "sendTransaction" => {
+            sends_seen.fetch_add(1, Ordering::SeqCst);
+            json!(sent_signature(params))
+        }
+        "getBlockHeight" => json!(50),
+        // Processed on the first poll, confirmed on the second
+        "getSignatureStatuses" => {
+            let confirmation = match polls_seen.fetch_add(1, Ordering::SeqCst) {
+                0 => "processed",
+                _ => "confirmed",
+            };
+            status_response(json!({
+                "slot": 7,
+                "confirmations": 0,
+                "err": null,
+                "status": { "Ok": null },
+                "confirmationStatus": confirmation,
+            }))
+        }
+        _ => Value::Null,
+    })
+    .await;
+
+    let payer = Keypair::new();
+    let tracked = tracker(url)
+        .send_and_confirm(|blockhash| noop_transaction(&payer, blockhash), 3)
+        .await
+        .unwrap();
+
+    assert_eq!(tracked.status, TxStatus::Confirmed { slot: 7 });
+    assert!(tracked.is_confirmed());
+    assert_eq!(tracked.attempts, 1);
+    assert_eq!(polls.load(Ordering::SeqCst), 2);
+    // Sent once, then rebroadcast while still processing
+    assert_eq!(sends.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn reports_a_failed_transaction_without_retrying() {
+    let url = serve_rpc(|method, params| match method {
+        "getLatestBlockhash" => blockhash_response(Hash::new_unique(), 100),
+        "sendTransaction" => json!(sent_signature(params)),
+        "getBlockHeight" => json!(50),
+        "getSignatureStatuses" => status_response(json!({
+            "slot": 9,
+            "confirmations": 0,
+            "err": { "InstructionError": [2, { "Custom": 6002 }] },
+            "status": { "Err": { "InstructionError": [2, { "Custom": 6002 }] } },
+            "confirmationStatus": "confirmed",
+        })),
+        _ => Value::Null,
+    })
+    .await;
+
+    let payer = Keypair::new();
+    let tracked = tracker(url)
+        .send_and_confirm(|blockhash| noop_transaction(&payer, blockhash), 3)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        tracked.status,
+        TxStatus::Failed {
+            slot: 9,
+            error: TransactionError::InstructionError(2, InstructionError::Custom(6002)),
+        }
+    );
+    assert_eq!(tracked.attempts, 1);
+}
+
+#[tokio::test]
+async fn resigns_with_a_new_blockhash_after_expiry() {
+    // Every blockhash expires right away: valid up to the current height, which then moves on
+    let height = Arc::new(AtomicU64::new(100));
+    let blockhashes = Arc::new(Mutex::new(Vec::new()));
+    let (height_seen, blockhashes_seen) = (height.clone(), blockhashes.clone());
+    let url = serve_rpc(move |method, params| match method {
+        "getLatestBlockhash" => {
+            let blockhash = Hash::new_unique();
+            blockhashes_seen.lock().unwrap().push(blockhash);
+            blockhash_response(blockhash, height_seen.load(Ordering::SeqCst))
+        }
+        "sendTransaction" => json!(sent_signature(params)),
+        "getBlockHeight" => json!(height_seen.fetch_add(1, Ordering::SeqCst) + 1),
+        "getSignatureStatuses" => status_response(Value::Null),
+        _ => Value::Null,
+    })
+    .await;
+
+    let payer = Keypair::new();
+    let signed = Arc::new(Mutex::new(Vec::new()));
+    let tracked = tracker(url)
+        .send_and_confirm(
+            |blockhash| {
+                signed.lock().unwrap().push(blockhash);
+                noop_transaction(&payer, blockhash)
+            },
+            2,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(tracked.status, TxStatus::Expired);
+    assert_eq!(tracked.attempts, 2);
+    let signed = signed.lock().unwrap();
+    assert_eq!(*signed, *blockhashes.lock().unwrap());
+    assert_eq!(signed.len(), 2);
+    assert_ne!(signed[0], signed[1]);
+}
+
+#[tokio::test]
+async fn keeps_watching_through_rpc_errors() {
+    let blockhashes = Arc::new(AtomicUsize::new(0));
+    let heights = Arc::new(AtomicUsize::new(0));
+    let polls = Arc::new(AtomicUsize::new(0));
+    let (blockhashes_seen, heights_seen, polls_seen) =
+        (blockhashes.clone(), heights.clone(), polls.clone());
+    let url = serve_rpc_or_error(move |method, params| match method {
+        "getLatestBlockhash" => {
+            blockhashes_seen.fetch_add(1, Ordering::SeqCst);
+            Ok(blockhash_response(Hash::new_unique(), 100))
+        }
+        "sendTransaction" => Ok(json!(sent_signature(params))),
+        // Both calls fail once, the transaction lands meanwhile
+        "getBlockHeight" if heights_seen.fetch_add(1, Ordering::SeqCst) == 0 => {
+            Err("node is behind".to_string())
+        }
+        "getBlockHeight" => Ok(json!(50)),
+        "getSignatureStatuses" if polls_seen.fetch_add(1, Ordering::SeqCst) == 0 => {
+            Err("node is behind".to_string())
+        }
+        "getSignatureStatuses" => Ok(status_response(json!({
+            "slot": 8,
+            "confirmations": 0,
+            "err": null,
+            "status": { "Ok": null },
+            "confirmationStatus": "confirmed",
+        }))),
+        _ => Ok(Value::Null),
+    })
+    .await;
+
+    let payer = Keypair::new();
+    let tracked = tracker(url)
+        .send_and_confirm(|blockhash| noop_transaction(&payer, blockhash), 3)
+        .await
+        .unwrap();
+
+    assert_eq!(tracked.status, TxStatus::Confirmed { slot: 8 });
+    // Never re-signed, the first transaction was still in flight
+    assert_eq!(tracked.attempts, 1);
+    assert_eq!(blockhashes.load(Ordering::SeqCst), 1);
+    assert_eq!(polls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn settles_a_transaction_sent_elsewhere_without_rebroadcasting() {
+    let sends = Arc::new(AtomicUsize::new(0));
+    let height = Arc::new(AtomicU64::new(98));
+    let (sends_seen, height_seen) = (sends.clone(), height.clone());
+    let url = serve_rpc(move |method, _| match method {
+        "sendTransaction" => {
+            sends_seen.fetch_add(1, Ordering::SeqCst);
+            Value::Null
+        }
+        "getBlockHeight" => json!(height_seen.fetch_add(1, Ordering::SeqCst)),
+        "getSignatureStatuses" => status_response(Value::Null),
+        _ => Value::Null,
+    })
+    .await;
+
+    let signature = noop_transaction(&Keypair::new(), Hash::new_unique()).signatures[0];
+    let status = tracker(url).wait_until_settled(&signature, 100).await;
+
+    // Polled until the height passed 100, never sent
+    assert_eq!(status, None);
+    assert_eq!(height.load(Ordering::SeqCst), 102);
+    assert_eq!(sends.load(Ordering::SeqCst), 0);
+}
+
+fn token_balance(account_index: u8, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Value {
+    json!({
+        "accountIndex": account_index,
+        "mint": mint.to_string(),
+        "owner": owner.to_string(),
+        "programId": spl_token::id().to_string(),
+        "uiTokenAmount": {
+            "amount": amount.to_string(),
+            "decimals": 6,
+            "uiAmount": amount as f64 / 1e6,
+            "uiAmountString": (amount as f64 / 1e6).to_string(),
+        },
+    })
+}
+
+// `Program data:` log of a pump.fun TradeEvent
+fn trade_event_log(mint: &Pubkey, user: &Pubkey, sol_amount: u64, token_amount: u64) -> String {
+    let mut data = vec![189, 219, 127, 211, 78, 230, 97, 238];
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&sol_amount.to_le_bytes());
+    data.extend_from_slice(&token_amount.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(user.as_ref());
+    data.extend_from_slice(&0i64.to_le_bytes());
+    data.extend_from_slice(&30_000_000_000u64.to_le_bytes());
+    data.extend_from_slice(&1_000_000_000_000_000u64.to_le_bytes());
+    format!("Program data: {}", STANDARD.encode(data))
+}
+
+#[tokio::test]
+async fn balance_change_comes_from_the_transaction_meta() {
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let transaction = noop_transaction(&payer, Hash::new_unique());
+    let encoded = STANDARD.encode(bincode::serialize(&transaction).unwrap());
+    let owner = payer.pubkey();
+    // Another trader's event first, then ours
+    let logs = vec![
+        format!("Program {} invoke [1]", PUMP_PROGRAM_ID),
+        trade_event_log(&mint, &Pubkey::new_unique(), 5, 5),
+        trade_event_log(&mint, &owner, 99_000_000, 2_500),
+        format!("Program {} success", PUMP_PROGRAM_ID),
+    ];
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls_seen = calls.clone();
+    let url = serve_rpc(move |method, _| match method {
+        // Not served on the first ask, as right after confirmation
+        "getTransaction" if calls_seen.fetch_add(1, Ordering::SeqCst) == 0 => Value::Null,
+        "getTransaction" => json!({
+            "slot": 10,
+            "blockTime": null,
+            "transaction": [encoded, "base64"],
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5_000,
+                "preBalances": [1_000_000_000, 1],
+                "postBalances": [900_000_000, 1],
+                "innerInstructions": [],
+                "logMessages": logs,
+                "preTokenBalances": [token_balance(3, &mint, &owner, 100), token_balance(4, &mint, &Pubkey::new_unique(), 7)],
+                "postTokenBalances": [token_balance(3, &mint, &owner, 2_600)],
+                "rewards": [],
+            },
+        }),
+        _ => Value::Null,
+    })
+    .await;
+
+    let change = monitor::tracker::fetch_balance_change(
+        &RpcClient::new(url),
+        &transaction.signatures[0],
+        &owner,
+        &mint,
+    )
+    .await
+    .unwrap();
+    assert_eq!(change.lamports, -100_000_000);
+    assert_eq!(change.tokens, 2_500);
+    assert_eq!(change.curve_lamports, Some(99_000_000));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}